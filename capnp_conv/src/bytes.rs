@@ -0,0 +1,289 @@
+use std::io::{BufRead, Write};
+
+use capnp::{
+    message::{
+        self, Allocator, HeapAllocator, ReaderOptions, ReaderSegments, ScratchSpaceHeapAllocator,
+        SUGGESTED_FIRST_SEGMENT_WORDS,
+    },
+    serialize, serialize_packed,
+    traits::Owned,
+    Error, Result, Word,
+};
+
+use crate::{read_parallel, ReadError, Readable, Writable};
+
+/// The largest segment `HeapAllocator` allocates
+pub(crate) const MAX_SEGMENT_WORDS: u32 = 1 << 29;
+
+/// How messages are built and read by [`WritableExt`] and [`ReadableExt`], for reads and writes
+/// that need other than the defaults:
+///
+/// ```
+/// let options = capnp_conv::MessageOptions {
+///     first_segment_words: 1 << 16,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct MessageOptions {
+    /// The limits capnp checks when reading a message.
+    pub reader_options: ReaderOptions,
+    /// The size of the first segment of a written message, later segments grow from it.
+    pub first_segment_words: u32,
+}
+
+impl Default for MessageOptions {
+    fn default() -> Self {
+        MessageOptions {
+            reader_options: ReaderOptions::new(),
+            first_segment_words: SUGGESTED_FIRST_SEGMENT_WORDS,
+        }
+    }
+}
+
+impl MessageOptions {
+    /// The default options, with a first segment that fits `value` and the pointer to it, so
+    /// that it is written to a single segment.
+    ///
+    /// The size is [`Writable::encoded_size_words`], up to the largest segment capnp allocates.
+    /// An estimate of 0, which manual impls that do not implement it return, keeps the default
+    /// size.
+    #[must_use]
+    pub fn sized_for<T: Writable + ?Sized>(value: &T) -> Self {
+        let first_segment_words = match value.encoded_size_words() {
+            0 => SUGGESTED_FIRST_SEGMENT_WORDS,
+            words => u32::try_from(words.saturating_add(1))
+                .unwrap_or(MAX_SEGMENT_WORDS)
+                .min(MAX_SEGMENT_WORDS),
+        };
+        MessageOptions {
+            first_segment_words,
+            ..MessageOptions::default()
+        }
+    }
+
+    /// Writes `value` to the root of a new message.
+    pub fn write_message<T: Writable + ?Sized>(
+        &self,
+        value: &T,
+    ) -> Result<message::Builder<HeapAllocator>> {
+        let allocator = HeapAllocator::new().first_segment_words(self.first_segment_words);
+        write_root(message::Builder::new(allocator), value)
+    }
+
+    /// Writes `value` to the root of a new message whose first segment is `scratch`, so that no
+    /// segment is allocated when it fits. Later segments grow from `first_segment_words`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scratch` is not 8-byte aligned, unless capnp's `unaligned` feature is enabled.
+    pub fn write_message_with_scratch<'a, T: Writable + ?Sized>(
+        &self,
+        value: &T,
+        scratch: &'a mut [u8],
+    ) -> Result<message::Builder<ScratchSpaceHeapAllocator<'a>>> {
+        let allocator =
+            ScratchSpaceHeapAllocator::new(scratch).second_segment_words(self.first_segment_words);
+        write_root(message::Builder::new(allocator), value)
+    }
+
+    /// Writes `value` as a message in the standard encoding, with a segment table.
+    pub fn write_bytes<T: Writable + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        Ok(serialize::write_message_to_words(
+            &self.write_message(value)?,
+        ))
+    }
+
+    /// Writes `value` as a packed message.
+    pub fn write_packed_bytes<T: Writable + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        serialize_packed::write_message(&mut bytes, &self.write_message(value)?)?;
+        Ok(bytes)
+    }
+
+    /// Writes `value` to `write` as a message in the standard encoding.
+    pub fn write_to<T: Writable + ?Sized>(&self, value: &T, write: impl Write) -> Result<()> {
+        serialize::write_message(write, &self.write_message(value)?)
+    }
+
+    /// Writes `value` to `write` as a message in the standard encoding, building it in `scratch`.
+    ///
+    /// See [`write_message_with_scratch`](MessageOptions::write_message_with_scratch).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scratch` is not 8-byte aligned, unless capnp's `unaligned` feature is enabled.
+    pub fn write_to_with_scratch<T: Writable + ?Sized>(
+        &self,
+        value: &T,
+        scratch: &mut [u8],
+        write: impl Write,
+    ) -> Result<()> {
+        serialize::write_message(write, &self.write_message_with_scratch(value, scratch)?)
+    }
+
+    /// Writes `value` as the bare words of a single segment, with no segment table.
+    ///
+    /// Fails if the message does not fit in `first_segment_words`. The length of the message is
+    /// left to the framing around it, read it back with
+    /// [`read_single_segment`](MessageOptions::read_single_segment).
+    pub fn write_single_segment<T: Writable + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        let message = self.write_message(value)?;
+        let segments = message.get_segments_for_output();
+        match &*segments {
+            [segment] => Ok(segment.to_vec()),
+            segments => Err(Error::failed(format!(
+                "capnp_conv: message does not fit in a single segment of {} words, it takes {} \
+                segments",
+                self.first_segment_words,
+                segments.len()
+            ))),
+        }
+    }
+
+    /// Reads a message in the standard encoding from the start of `bytes`, copying its segments.
+    pub fn read_bytes<T: Readable>(&self, mut bytes: &[u8]) -> ReadResult<T> {
+        let message =
+            serialize::read_message(&mut bytes, self.reader_options).map_err(read_error::<T>)?;
+        read_parallel(message.into_segments(), self.reader_options)
+    }
+
+    /// Reads a packed message from `read`.
+    pub fn read_packed<T: Readable>(&self, read: impl BufRead) -> ReadResult<T> {
+        let message =
+            serialize_packed::read_message(read, self.reader_options).map_err(read_error::<T>)?;
+        read_parallel(message.into_segments(), self.reader_options)
+    }
+
+    /// Reads a message in the standard encoding from the start of `slice` without copying it,
+    /// then advances `slice` past the message.
+    ///
+    /// `slice` must be 8-byte aligned, unless capnp's `unaligned` feature is enabled. The message
+    /// is borrowed, so `#[capnp_conv(parallel)]` lists are read in order.
+    pub fn read_flat_slice<T: Readable>(&self, slice: &mut &[u8]) -> ReadResult<T> {
+        let message = serialize::read_message_from_flat_slice(slice, self.reader_options)
+            .map_err(read_error::<T>)?;
+        read_root(&message)
+    }
+
+    /// Reads the bare words of a single segment, as written by
+    /// [`write_single_segment`](MessageOptions::write_single_segment).
+    ///
+    /// `bytes` must be exactly the segment. It is copied if it is not 8-byte aligned. The segment
+    /// is borrowed, so `#[capnp_conv(parallel)]` lists are read in order.
+    pub fn read_single_segment<T: Readable>(&self, bytes: &[u8]) -> ReadResult<T> {
+        if !bytes.len().is_multiple_of(8) {
+            return Err(read_error::<T>(Error::failed(format!(
+                "capnp_conv: a segment is a whole number of words, got {} bytes",
+                bytes.len()
+            ))));
+        }
+        if (bytes.as_ptr() as usize).is_multiple_of(8) {
+            let segments: &[&[u8]] = &[bytes];
+            read_root(&message::Reader::new(segments, self.reader_options))
+        } else {
+            let words = to_words(bytes);
+            let segments: &[&[u8]] = &[Word::words_to_bytes(&words)];
+            read_root(&message::Reader::new(segments, self.reader_options))
+        }
+    }
+}
+
+/// Copies `bytes` into words, which are aligned. The last word is padded with zeros.
+pub(crate) fn to_words(bytes: &[u8]) -> Vec<Word> {
+    let mut words = Word::allocate_zeroed_vec(bytes.len().div_ceil(8));
+    Word::words_to_bytes_mut(&mut words)[..bytes.len()].copy_from_slice(bytes);
+    words
+}
+
+pub(crate) fn write_root<A: Allocator, T: Writable + ?Sized>(
+    mut message: message::Builder<A>,
+    value: &T,
+) -> Result<message::Builder<A>> {
+    value.try_write(message.init_root::<<T::OwnedType as Owned>::Builder<'_>>())?;
+    Ok(message)
+}
+
+pub(crate) type ReadResult<T> = core::result::Result<T, ReadError<<T as Readable>::Error>>;
+
+fn read_error<T: Readable>(error: Error) -> ReadError<T::Error> {
+    ReadError::new(T::Error::from(error))
+}
+
+pub(crate) fn read_root<T: Readable, S: ReaderSegments>(
+    message: &message::Reader<S>,
+) -> ReadResult<T> {
+    let root = message
+        .get_root::<<T::OwnedType as Owned>::Reader<'_>>()
+        .map_err(read_error::<T>)?;
+    T::read_with_path(root)
+}
+
+/// Writes [`Writable`] values as serialized messages, with a single segment that is sized by
+/// [`MessageOptions::sized_for`].
+pub trait WritableExt: Writable {
+    /// Writes the value as a message in the standard encoding, with a segment table.
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        MessageOptions::sized_for(self).write_bytes(self)
+    }
+
+    /// Writes the value as a packed message.
+    fn to_packed_bytes(&self) -> Result<Vec<u8>> {
+        MessageOptions::sized_for(self).write_packed_bytes(self)
+    }
+
+    /// Writes the value to `write` as a message in the standard encoding.
+    fn write_to(&self, write: impl Write) -> Result<()> {
+        MessageOptions::sized_for(self).write_to(self, write)
+    }
+
+    /// Writes the value to `write` as a message in the standard encoding, building it in
+    /// `scratch`, which must be 8-byte aligned.
+    ///
+    /// See [`MessageOptions::write_message_with_scratch`].
+    fn write_to_with_scratch(&self, scratch: &mut [u8], write: impl Write) -> Result<()> {
+        MessageOptions::sized_for(self).write_to_with_scratch(self, scratch, write)
+    }
+
+    /// Writes the value as the bare words of a single segment, with no segment table.
+    ///
+    /// See [`MessageOptions::write_single_segment`].
+    fn to_single_segment_bytes(&self) -> Result<Vec<u8>> {
+        MessageOptions::sized_for(self).write_single_segment(self)
+    }
+}
+
+impl<T: Writable + ?Sized> WritableExt for T {}
+
+/// Reads [`Readable`] values from serialized messages, with the default [`MessageOptions`].
+///
+/// Messages that are copied are read with [`read_parallel`], so `#[capnp_conv(parallel)]` lists
+/// are read in parallel.
+pub trait ReadableExt: Readable {
+    /// Reads a message in the standard encoding from the start of `bytes`.
+    fn from_bytes(bytes: &[u8]) -> ReadResult<Self> {
+        MessageOptions::default().read_bytes(bytes)
+    }
+
+    /// Reads a packed message from `read`.
+    fn from_packed(read: impl BufRead) -> ReadResult<Self> {
+        MessageOptions::default().read_packed(read)
+    }
+
+    /// Reads a message in the standard encoding from the start of `slice` without copying it,
+    /// then advances `slice` past the message.
+    ///
+    /// See [`MessageOptions::read_flat_slice`].
+    fn from_flat_slice(slice: &mut &[u8]) -> ReadResult<Self> {
+        MessageOptions::default().read_flat_slice(slice)
+    }
+
+    /// Reads the bare words of a single segment.
+    ///
+    /// See [`MessageOptions::read_single_segment`].
+    fn from_single_segment(bytes: &[u8]) -> ReadResult<Self> {
+        MessageOptions::default().read_single_segment(bytes)
+    }
+}
+
+impl<T: Readable> ReadableExt for T {}
@@ -0,0 +1,163 @@
+use core::{
+    any::Any,
+    fmt::{self, Display, Formatter, Write},
+    str::Utf8Error,
+};
+
+use capnp::{Error, NotInSchema};
+
+/// A step in the path from the value being read to the value that failed to read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// A struct field or union variant, by its rust and capnp schema names.
+    Field {
+        rust_name: &'static str,
+        capnp_name: &'static str,
+    },
+    /// An element of a list.
+    Index(u32),
+}
+
+/// A read error along with the path to the value that failed to read, returned by
+/// [`Readable::read_with_path`](crate::Readable::read_with_path).
+///
+/// `E` is the [`Readable::Error`](crate::Readable::Error) of the outermost type.
+#[derive(Debug, Clone)]
+pub struct ReadError<E = Error> {
+    /// The outermost type that was read, if it was generated by `capnp_conv`.
+    pub root: Option<&'static str>,
+    /// The path from `root` to the value that failed to read, innermost first, as segments are
+    /// added while the error is returned from nested reads.
+    pub path: Vec<PathSegment>,
+    /// The underlying error.
+    pub cause: E,
+}
+
+impl<E> ReadError<E> {
+    /// Creates an error with no path.
+    pub fn new(cause: E) -> Self {
+        ReadError {
+            root: None,
+            path: Vec::new(),
+            cause,
+        }
+    }
+
+    /// Converts the cause to the error type of an enclosing read, keeping the path.
+    pub fn convert<F: From<E>>(self) -> ReadError<F> {
+        ReadError {
+            root: self.root,
+            path: self.path,
+            cause: F::from(self.cause),
+        }
+    }
+
+    /// Converts the cause to a `capnp::Error`, keeping the path, for reads nested in an item whose
+    /// error type is `capnp::Error`.
+    ///
+    /// A `capnp::Error` cause is kept as it is, other causes become a failed `capnp::Error` with
+    /// their message.
+    #[must_use]
+    pub fn into_capnp(self) -> ReadError
+    where
+        E: Display + 'static,
+    {
+        let cause = match (&self.cause as &dyn Any).downcast_ref::<Error>() {
+            Some(error) => error.clone(),
+            None => Error::failed(self.cause.to_string()),
+        };
+        ReadError {
+            root: self.root,
+            path: self.path,
+            cause,
+        }
+    }
+
+    /// Adds the field or union variant the error happened in to the outer end of the path.
+    #[must_use]
+    pub fn in_field(mut self, rust_name: &'static str, capnp_name: &'static str) -> Self {
+        self.path.push(PathSegment::Field {
+            rust_name,
+            capnp_name,
+        });
+        self
+    }
+
+    /// Adds the list element the error happened in to the outer end of the path.
+    #[must_use]
+    pub fn at_index(mut self, index: u32) -> Self {
+        self.path.push(PathSegment::Index(index));
+        self
+    }
+
+    /// Sets the type the error happened in, replacing that of any nested read.
+    #[must_use]
+    pub fn in_type(mut self, root: &'static str) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Formats the path with rust names, like `Outer.list[3].field`.
+    #[must_use]
+    pub fn path_string(&self) -> String {
+        let mut path = String::from(self.root.unwrap_or_default());
+        for segment in self.path.iter().rev() {
+            match segment {
+                PathSegment::Field { rust_name, .. } if path.is_empty() => path.push_str(rust_name),
+                PathSegment::Field { rust_name, .. } => {
+                    path.push('.');
+                    path.push_str(rust_name);
+                }
+                PathSegment::Index(index) => {
+                    let _ = write!(path, "[{index}]");
+                }
+            }
+        }
+        path
+    }
+}
+
+impl<E: Display> Display for ReadError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let path = self.path_string();
+        if path.is_empty() {
+            write!(f, "{}", self.cause)
+        } else {
+            write!(f, "at `{path}`: {}", self.cause)
+        }
+    }
+}
+
+impl<E: fmt::Debug + Display> std::error::Error for ReadError<E> {}
+
+impl<E: From<Error>> From<Error> for ReadError<E> {
+    fn from(cause: Error) -> Self {
+        ReadError::new(E::from(cause))
+    }
+}
+
+impl<E: From<Error>> From<NotInSchema> for ReadError<E> {
+    fn from(cause: NotInSchema) -> Self {
+        Error::from(cause).into()
+    }
+}
+
+impl<E: From<Error>> From<Utf8Error> for ReadError<E> {
+    fn from(cause: Utf8Error) -> Self {
+        Error::from(cause).into()
+    }
+}
+
+/// Keeps the error kind, adding the path to the description.
+impl From<ReadError> for Error {
+    fn from(error: ReadError) -> Self {
+        let path = error.path_string();
+        let Error { kind, extra } = error.cause;
+        let extra = match (path.is_empty(), extra.is_empty()) {
+            (true, _) => extra,
+            (false, true) => format!("at `{path}`"),
+            (false, false) => format!("{extra} (at `{path}`)"),
+        };
+        Error { kind, extra }
+    }
+}
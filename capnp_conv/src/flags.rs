@@ -0,0 +1,45 @@
+use core::{any::type_name, fmt::LowerHex};
+
+use bitflags::Flags;
+use capnp::{Error, Result};
+
+/// How [`read_flags`] handles set bits that the flags type does not define.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndefinedBits {
+    /// Keep the bits, so they are written back unchanged
+    Retain,
+    /// Drop the bits
+    Truncate,
+    /// Fail the read
+    Error,
+}
+
+/// Reads a `bitflags` type from the raw bits of an integer field.
+pub fn read_flags<F>(bits: F::Bits, undefined_bits: UndefinedBits) -> Result<F>
+where
+    F: Flags,
+    F::Bits: LowerHex,
+{
+    match undefined_bits {
+        UndefinedBits::Retain => Ok(F::from_bits_retain(bits)),
+        UndefinedBits::Truncate => Ok(F::from_bits_truncate(bits)),
+        UndefinedBits::Error => F::from_bits(bits).ok_or_else(|| {
+            Error::failed(format!(
+                "capnp_conv: undefined bits {:#x} set in `{}`",
+                bits & !F::all().bits(),
+                type_name::<F>()
+            ))
+        }),
+    }
+}
+
+/// Returns the raw bits of a `bitflags` type for writing to an integer field.
+pub fn flags_bits<F: Flags>(flags: &F) -> F::Bits {
+    flags.bits()
+}
+
+/// The bits per element of a list of a `bitflags` type.
+#[must_use]
+pub fn flags_bit_width<F: Flags>() -> u64 {
+    core::mem::size_of::<F::Bits>() as u64 * 8
+}
@@ -0,0 +1,171 @@
+use std::{fmt, iter::FusedIterator, ops::Range};
+
+use capnp::{
+    struct_list,
+    traits::{Owned, OwnedStruct},
+};
+
+use crate::{bytes::ReadResult, Readable};
+
+/// A list of structs that reads each item when it is needed, instead of all of them up front
+/// like a `Vec<T>` field.
+///
+/// Items are read with [`Readable::read_with_path`], and their errors start with their index.
+/// Errors of items that are `capnp::Error` convert back to it with `?`.
+pub struct LazyList<'a, T: Readable>
+where
+    T::OwnedType: OwnedStruct,
+{
+    reader: struct_list::Reader<'a, T::OwnedType>,
+}
+
+impl<'a, T> LazyList<'a, T>
+where
+    T: Readable,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>: Into<<T::OwnedType as Owned>::Reader<'b>>,
+{
+    #[must_use]
+    pub fn new(reader: struct_list::Reader<'a, T::OwnedType>) -> Self {
+        LazyList { reader }
+    }
+
+    /// The reader of the list, which reads nothing itself.
+    #[must_use]
+    pub fn reader(&self) -> struct_list::Reader<'a, T::OwnedType> {
+        self.reader
+    }
+
+    #[must_use]
+    pub fn len(&self) -> u32 {
+        self.reader.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.reader.is_empty()
+    }
+
+    /// Reads the item at `index`, `None` if it is past the end of the list.
+    #[must_use]
+    pub fn get(&self, index: u32) -> Option<ReadResult<T>> {
+        let reader = self.reader.try_get(index)?;
+        Some(T::read_with_path(reader.into()).map_err(|error| error.at_index(index)))
+    }
+
+    /// Reads the items in order.
+    #[must_use]
+    pub fn iter(&self) -> LazyListIter<'a, T> {
+        LazyListIter {
+            list: *self,
+            indices: 0..self.len(),
+        }
+    }
+}
+
+impl<T: Readable> Clone for LazyList<'_, T>
+where
+    T::OwnedType: OwnedStruct,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Readable> Copy for LazyList<'_, T> where T::OwnedType: OwnedStruct {}
+
+impl<T: Readable> fmt::Debug for LazyList<'_, T>
+where
+    T::OwnedType: OwnedStruct,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyList")
+            .field("len", &self.reader.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T> IntoIterator for LazyList<'a, T>
+where
+    T: Readable,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>: Into<<T::OwnedType as Owned>::Reader<'b>>,
+{
+    type Item = ReadResult<T>;
+    type IntoIter = LazyListIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &LazyList<'a, T>
+where
+    T: Readable,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>: Into<<T::OwnedType as Owned>::Reader<'b>>,
+{
+    type Item = ReadResult<T>;
+    type IntoIter = LazyListIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Reads the items of a [`LazyList`] in order.
+pub struct LazyListIter<'a, T: Readable>
+where
+    T::OwnedType: OwnedStruct,
+{
+    list: LazyList<'a, T>,
+    indices: Range<u32>,
+}
+
+impl<T> Iterator for LazyListIter<'_, T>
+where
+    T: Readable,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>: Into<<T::OwnedType as Owned>::Reader<'b>>,
+{
+    type Item = ReadResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.get(self.indices.next()?)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.list.get(self.indices.nth(n)?)
+    }
+}
+
+impl<T> DoubleEndedIterator for LazyListIter<'_, T>
+where
+    T: Readable,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>: Into<<T::OwnedType as Owned>::Reader<'b>>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.get(self.indices.next_back()?)
+    }
+}
+
+impl<T> ExactSizeIterator for LazyListIter<'_, T>
+where
+    T: Readable,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>: Into<<T::OwnedType as Owned>::Reader<'b>>,
+{
+}
+
+impl<T> FusedIterator for LazyListIter<'_, T>
+where
+    T: Readable,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>: Into<<T::OwnedType as Owned>::Reader<'b>>,
+{
+}
@@ -1,21 +1,399 @@
-use capnp::{traits::Owned, Result};
+pub use bytes::{MessageOptions, ReadableExt, WritableExt};
+use capnp::{
+    dynamic_list, dynamic_struct, dynamic_value, enum_list,
+    introspect::{Introspect, TypeVariant},
+    traits::Owned,
+    Error, NotInSchema, Result,
+};
 pub use capnp_conv_macros::capnp_conv;
+pub use error::{PathSegment, ReadError};
+#[cfg(feature = "bitflags")]
+pub use flags::{flags_bit_width, flags_bits, read_flags, UndefinedBits};
+pub use lazy_list::{LazyList, LazyListIter};
+pub use limits::{ReadContext, ReadLimits};
+pub use list::{read_primitive_list, write_primitive_list, write_struct_list, ListPrimitive};
+pub use message::Message;
+pub use parallel::{read_list_parallel, read_parallel, PARALLEL_MIN_LEN};
+pub use pool::{BuilderPool, PooledAllocator};
+pub use size::{data_words, list_words, struct_words, text_words};
+pub use union::set_union_discriminant;
+
+mod bytes;
+mod error;
+#[cfg(feature = "bitflags")]
+mod flags;
+mod lazy_list;
+mod limits;
+mod list;
+mod message;
+mod parallel;
+mod pool;
+mod size;
+mod union;
+
+/// Expands to the call to a function of the `bitflags` feature in the code generated for
+/// `type = "bitflags"` fields.
+#[doc(hidden)]
+#[cfg(feature = "bitflags")]
+#[macro_export]
+macro_rules! bitflags_call {
+    ($($call:tt)*) => {
+        $($call)*
+    };
+}
+
+/// Fails the build of the code generated for `type = "bitflags"` fields, which needs the
+/// `bitflags` feature.
+#[doc(hidden)]
+#[cfg(not(feature = "bitflags"))]
+#[macro_export]
+macro_rules! bitflags_call {
+    ($($call:tt)*) => {
+        compile_error!("type = \"bitflags\" requires the capnp_conv `bitflags` feature")
+    };
+}
 
 pub trait Writable {
     type OwnedType: Owned;
 
+    /// Writes the value to `builder`.
+    ///
+    /// # Panics
+    ///
+    /// Generated impls panic on numbers or int enum codes that do not fit in their capnp field,
+    /// and on writes to the builder that fail, with the error [`Writable::try_write`] returns for
+    /// them.
     fn write(&self, builder: <Self::OwnedType as Owned>::Builder<'_>);
+
+    /// Writes like [`Writable::write`], returning an error for values the schema cannot hold.
+    ///
+    /// Generated impls fail on out of range numbers or int enum codes, lists, `Text`, or `Data`
+    /// longer than [`MAX_LIST_LEN`], or several `union_variant` fields that are set at once, where
+    /// `write` panics, truncates the length, or writes the last field set.
+    fn try_write(&self, builder: <Self::OwnedType as Owned>::Builder<'_>) -> Result<()> {
+        self.write(builder);
+        Ok(())
+    }
+
+    /// The words the value takes in a message, not counting the pointer to it.
+    ///
+    /// Generated impls sum the struct sections from the capnp generated code with the sizes of
+    /// `Text`, `Data`, and lists. Lists of `int_enum`s are counted as 64 bits per element, so the
+    /// size is an upper bound for them. The default impl returns 0.
+    ///
+    /// The size is that of the value as written, so generated impls run the `before_write` hooks
+    /// of the item and its fields, which the `WritableExt` methods then run again to write it.
+    fn encoded_size_words(&self) -> u64 {
+        0
+    }
 }
 
+/// Reads a value from a capnp reader.
+///
+/// [`Readable::read`] always returns a `capnp::Error`, whatever the [`Readable::Error`] of the
+/// item is. Items with an `error = "..."` type return it from [`Readable::read_with_path`], as the
+/// `cause` of its [`ReadError`], and from their `TryFrom<Reader>` impl, while `read` formats it
+/// into the message of a `capnp::Error`.
 pub trait Readable
 where
     Self: Sized,
 {
     type OwnedType: Owned;
+    /// The cause of the errors of [`Readable::read_with_path`] and the other reads that return a
+    /// [`ReadError`], `capnp::Error` unless set with `#[capnp_conv(capnp_path, error = "...")]`.
+    type Error: From<Error> + core::fmt::Debug + core::fmt::Display + 'static;
 
+    /// Reads the value, returning a `capnp::Error` whatever the [`Readable::Error`] of the item
+    /// is, use [`Readable::read_with_path`] to get that error.
     fn read(reader: <Self::OwnedType as Owned>::Reader<'_>) -> Result<Self>;
+
+    /// Reads like [`Readable::read`], keeping track of the path to the value that failed to read.
+    ///
+    /// Generated impls add the path to the errors returned by `read` as well, formatting errors
+    /// of other types than `capnp::Error` into it.
+    fn read_with_path(
+        reader: <Self::OwnedType as Owned>::Reader<'_>,
+    ) -> core::result::Result<Self, ReadError<Self::Error>> {
+        Self::read(reader).map_err(|error| ReadError::new(Self::Error::from(error)))
+    }
+
+    /// Reads like [`Readable::read_with_path`], replacing fields that fail to read with their
+    /// default value rather than failing, and returning the errors of those fields.
+    ///
+    /// Only the impls of items generated with the `lenient` option recover from field errors, the
+    /// default impl returns no field errors and fails like `read_with_path`. The read still fails
+    /// if the item itself cannot be read, such as a union with an unknown discriminant or an item
+    /// `validate` hook that fails.
+    #[allow(clippy::type_complexity)]
+    fn read_lenient(
+        reader: <Self::OwnedType as Owned>::Reader<'_>,
+    ) -> core::result::Result<(Self, Vec<ReadError<Self::Error>>), ReadError<Self::Error>> {
+        Self::read_with_path(reader).map(|value| (value, Vec::new()))
+    }
+
+    /// Reads like [`Readable::read_with_path`], failing if the read goes over `limits`.
+    fn read_with_limits(
+        reader: <Self::OwnedType as Owned>::Reader<'_>,
+        limits: ReadLimits,
+    ) -> core::result::Result<Self, ReadError<Self::Error>> {
+        Self::read_in_context(reader, &mut ReadContext::new(limits))
+    }
+
+    /// Reads like [`Readable::read_with_path`], checking lists, `Text`, `Data`, and nested items
+    /// against the limits of `context`.
+    ///
+    /// Only generated impls check the limits, the default impl reads like `read_with_path`.
+    fn read_in_context(
+        reader: <Self::OwnedType as Owned>::Reader<'_>,
+        context: &mut ReadContext,
+    ) -> core::result::Result<Self, ReadError<Self::Error>> {
+        let _ = context;
+        Self::read_with_path(reader)
+    }
+
+    /// Reads like [`Readable::read_with_path`] into `self`, reusing its allocations.
+    ///
+    /// Generated impls of structs overwrite their fields in place, keeping the capacity of their
+    /// `String` and `Vec` fields, and reading nested structs and the items of lists of structs
+    /// into the values they replace. The default impl, which is also used by generated unions,
+    /// structs with union fields, and items with `validate` or `after_read` hooks, replaces
+    /// `self` with the value read.
+    ///
+    /// When the read fails, `self` is left partly overwritten.
+    fn read_into(
+        &mut self,
+        reader: <Self::OwnedType as Owned>::Reader<'_>,
+    ) -> core::result::Result<(), ReadError<Self::Error>> {
+        *self = Self::read_with_path(reader)?;
+        Ok(())
+    }
+}
+
+/// A struct with a view, generated by `#[capnp_conv(capnp_struct, view)]`, that reads its
+/// fields from a reader when they are asked for rather than all at once.
+pub trait Viewable: Readable {
+    type View<'a>: Copy;
+
+    fn view(reader: <Self::OwnedType as Owned>::Reader<'_>) -> Self::View<'_>;
 }
 
 pub trait RemoteEnum<T> {
     fn to_capnp_enum(&self) -> T;
 }
+
+/// A rust enum stored as capnp `Text`, generated by `#[capnp_conv(type = "text_enum")]`.
+pub trait TextEnum
+where
+    Self: Sized,
+{
+    fn from_text(text: &str) -> Result<Self>;
+    fn to_text(&self) -> &'static str;
+}
+
+/// A rust enum stored as a capnp integer code, generated by `#[capnp_conv(type = "int_enum")]`.
+pub trait IntEnum
+where
+    Self: Sized,
+{
+    fn from_int(code: i64) -> Result<Self>;
+    fn to_int(&self) -> i64;
+}
+
+/// Reads an [`IntEnum`] from an integer field of any width.
+pub fn read_int_enum<T, C>(code: C) -> Result<T>
+where
+    T: IntEnum,
+    C: TryInto<i64> + Copy + core::fmt::Display,
+{
+    match code.try_into() {
+        Ok(code) => T::from_int(code),
+        Err(_) => Err(Error::failed(format!(
+            "capnp_conv: int enum code {code} is out of range"
+        ))),
+    }
+}
+
+/// Converts an [`IntEnum`] to the integer width of the field it is written to, failing if the
+/// code does not fit in the field.
+pub fn try_int_enum_code<C: TryFrom<i64>>(value: &impl IntEnum) -> Result<C> {
+    let code = value.to_int();
+    C::try_from(code).map_err(|_| {
+        Error::failed(format!(
+            "capnp_conv: int enum code {code} does not fit in its field"
+        ))
+    })
+}
+
+/// Converts a primitive read from the capnp field `field_name` to the type of its rust field.
+pub fn read_checked<C, T>(value: C, field_name: &str) -> Result<T>
+where
+    C: Copy + core::fmt::Display,
+    T: TryFrom<C>,
+{
+    T::try_from(value).map_err(|_| {
+        Error::failed(format!(
+            "capnp_conv: `{field_name}` value {value} is out of range for `{}`",
+            core::any::type_name::<T>()
+        ))
+    })
+}
+
+/// Converts a rust primitive to the type of the capnp field `field_name` it is written to,
+/// failing if the value does not fit in the capnp field.
+pub fn try_write_checked<T, C>(value: T, field_name: &str) -> Result<C>
+where
+    T: Copy + core::fmt::Display,
+    C: TryFrom<T>,
+{
+    C::try_from(value).map_err(|_| {
+        Error::failed(format!(
+            "capnp_conv: `{field_name}` value {value} is out of range for `{}`",
+            core::any::type_name::<C>()
+        ))
+    })
+}
+
+/// Unwraps the value of a conversion in [`Writable::write`], panicking with its error where
+/// [`Writable::try_write`] would return it.
+///
+/// # Panics
+///
+/// Panics if `value` is an error.
+#[track_caller]
+pub fn expect_written<T>(value: Result<T>) -> T {
+    match value {
+        Ok(value) => value,
+        Err(error) => panic!("{error}, use `try_write` to get the error instead"),
+    }
+}
+
+/// The most elements a capnp list can hold, which is also the most bytes of `Data` and `Text`
+/// (including its NUL terminator).
+pub const MAX_LIST_LEN: u32 = (1 << 29) - 1;
+
+/// Converts the length of a list written to the capnp field `field_name` to a capnp list size,
+/// failing if it is over [`MAX_LIST_LEN`].
+pub fn list_len(len: usize, field_name: &str) -> Result<u32> {
+    match u32::try_from(len) {
+        Ok(len) if len <= MAX_LIST_LEN => Ok(len),
+        _ => Err(Error::failed(format!(
+            "capnp_conv: `{field_name}` has {len} elements, more than the {MAX_LIST_LEN} that \
+            capnp can hold"
+        ))),
+    }
+}
+
+/// Fails if more than one of the `union_variant` fields of `type_name` is set, as a union only
+/// holds one of them.
+pub fn check_union_fields(type_name: &str, fields: &[(&str, bool)]) -> Result<()> {
+    let set_fields: Vec<&str> = fields
+        .iter()
+        .filter(|(_, is_set)| *is_set)
+        .map(|(name, _)| *name)
+        .collect();
+    if set_fields.len() > 1 {
+        Err(Error::failed(format!(
+            "capnp_conv: `{type_name}` has more than one union field set: `{}`",
+            set_fields.join("`, `")
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// A 128-bit integer, stored as a struct of two `UInt64` words (`hi` and `lo`) or as 16 bytes of
+/// big-endian `Data`.
+pub trait Int128
+where
+    Self: Sized,
+{
+    fn from_words(hi: u64, lo: u64) -> Self;
+    fn to_words(self) -> (u64, u64);
+    fn from_data(data: &[u8]) -> Result<Self>;
+    fn to_data(self) -> [u8; 16];
+}
+
+macro_rules! impl_int128 {
+    ($ty:ty) => {
+        impl Int128 for $ty {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            fn from_words(hi: u64, lo: u64) -> Self {
+                ((u128::from(hi) << 64) | u128::from(lo)) as $ty
+            }
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            fn to_words(self) -> (u64, u64) {
+                ((self >> 64) as u64, self as u64)
+            }
+            fn from_data(data: &[u8]) -> Result<Self> {
+                match data.try_into() {
+                    Ok(bytes) => Ok(<$ty>::from_be_bytes(bytes)),
+                    Err(_) => Err(Error::failed(format!(
+                        "capnp_conv: expected 16 bytes for `{}`, found {}",
+                        stringify!($ty),
+                        data.len()
+                    ))),
+                }
+            }
+            fn to_data(self) -> [u8; 16] {
+                self.to_be_bytes()
+            }
+        }
+    };
+}
+
+impl_int128!(u128);
+impl_int128!(i128);
+
+/// Returns the raw ordinal of a read enum value, including ones missing from the local schema.
+pub fn enum_ordinal<T: Into<u16>>(value: core::result::Result<T, NotInSchema>) -> u16 {
+    match value {
+        Ok(value) => value.into(),
+        Err(NotInSchema(ordinal)) => ordinal,
+    }
+}
+
+/// Sets the enum field named `field_name` (as spelled in the schema) to a raw ordinal that the
+/// generated capnp enum cannot represent.
+///
+/// Fails if the struct has no enum field named `field_name`.
+pub fn set_enum_ordinal<'a>(
+    builder: impl Into<dynamic_value::Builder<'a>>,
+    field_name: &str,
+    ordinal: u16,
+) -> Result<()> {
+    let mut builder = builder.into().downcast::<dynamic_struct::Builder>();
+    let field = builder.get_schema().get_field_by_name(field_name)?;
+    let TypeVariant::Enum(schema) = field.get_type().which() else {
+        return Err(Error::failed(format!(
+            "capnp_conv: `{field_name}` is not an enum field"
+        )));
+    };
+    builder.set(
+        field,
+        dynamic_value::Enum::new(ordinal, schema.into()).into(),
+    )
+}
+
+/// Sets the element at `index` of an enum list to a raw ordinal that the generated capnp enum
+/// cannot represent.
+///
+/// Fails if `index` is out of bounds.
+pub fn set_enum_list_ordinal<T>(
+    builder: enum_list::Builder<'_, T>,
+    index: u32,
+    ordinal: u16,
+) -> Result<()>
+where
+    T: TryFrom<u16, Error = NotInSchema> + Introspect,
+{
+    let mut builder = dynamic_value::Builder::from(builder).downcast::<dynamic_list::Builder>();
+    let TypeVariant::Enum(schema) = builder.element_type().which() else {
+        return Err(Error::failed(String::from(
+            "capnp_conv: enum ordinals can only be set in lists of enums",
+        )));
+    };
+    builder.set(
+        index,
+        dynamic_value::Enum::new(ordinal, schema.into()).into(),
+    )
+}
@@ -0,0 +1,139 @@
+use capnp::{Error, Result};
+
+/// Caps on what a read may allocate, checked by
+/// [`Readable::read_with_limits`](crate::Readable::read_with_limits).
+///
+/// The default has no limits, set the ones to enforce:
+///
+/// ```
+/// let limits = capnp_conv::ReadLimits {
+///     max_list_len: 10_000,
+///     max_total_bytes: 1 << 20,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// The most elements of a single list.
+    pub max_list_len: u32,
+    /// The most bytes of a single `Text`, not counting its NUL terminator.
+    pub max_text_len: u32,
+    /// The most bytes of a single `Data`.
+    pub max_data_len: u32,
+    /// The most generated items nested in each other, counting the outermost one.
+    pub max_depth: u32,
+    /// The most bytes allocated for lists, `Text`, and `Data` over the whole read.
+    pub max_total_bytes: u64,
+}
+
+impl ReadLimits {
+    /// No limits, beyond those of capnp itself.
+    pub const UNLIMITED: ReadLimits = ReadLimits {
+        max_list_len: u32::MAX,
+        max_text_len: u32::MAX,
+        max_data_len: u32::MAX,
+        max_depth: u32::MAX,
+        max_total_bytes: u64::MAX,
+    };
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        ReadLimits::UNLIMITED
+    }
+}
+
+/// The state of a read with [`ReadLimits`], passed to
+/// [`Readable::read_in_context`](crate::Readable::read_in_context).
+///
+/// Errors name the capnp field that went over a limit, and generated readers add its path.
+#[derive(Debug, Clone)]
+pub struct ReadContext {
+    limits: ReadLimits,
+    depth: u32,
+    total_bytes: u64,
+}
+
+impl ReadContext {
+    #[must_use]
+    pub fn new(limits: ReadLimits) -> Self {
+        ReadContext {
+            limits,
+            depth: 0,
+            total_bytes: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn limits(&self) -> &ReadLimits {
+        &self.limits
+    }
+
+    /// The bytes allocated so far.
+    #[must_use]
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// Starts reading a nested item named `type_name`, failing if it is nested too deep.
+    pub fn enter(&mut self, type_name: &str) -> Result<()> {
+        if self.depth >= self.limits.max_depth {
+            return Err(Error::failed(format!(
+                "capnp_conv: `{type_name}` is nested more than {} deep",
+                self.limits.max_depth
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Finishes reading the item of the last call to [`ReadContext::enter`].
+    pub fn exit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// Allocates the list of `len` elements read from the capnp field `field_name`.
+    pub fn list<T>(&mut self, len: u32, field_name: &str) -> Result<Vec<T>> {
+        if len > self.limits.max_list_len {
+            return Err(Error::failed(format!(
+                "capnp_conv: `{field_name}` has {len} elements, more than the limit of {}",
+                self.limits.max_list_len
+            )));
+        }
+        let bytes = u64::from(len).saturating_mul(core::mem::size_of::<T>() as u64);
+        self.allocate(bytes, field_name)?;
+        Ok(Vec::with_capacity(len as usize))
+    }
+
+    /// Checks a `Text` of `len` bytes read from the capnp field `field_name`, before it is copied.
+    pub fn text(&mut self, len: usize, field_name: &str) -> Result<()> {
+        self.blob(len, self.limits.max_text_len, "text", field_name)
+    }
+
+    /// Checks a `Data` of `len` bytes read from the capnp field `field_name`, before it is copied.
+    pub fn data(&mut self, len: usize, field_name: &str) -> Result<()> {
+        self.blob(len, self.limits.max_data_len, "data", field_name)
+    }
+
+    fn blob(&mut self, len: usize, max_len: u32, kind: &str, field_name: &str) -> Result<()> {
+        if len > max_len as usize {
+            return Err(Error::failed(format!(
+                "capnp_conv: `{field_name}` has {len} bytes of {kind}, more than the limit of \
+                {max_len}"
+            )));
+        }
+        self.allocate(len as u64, field_name)
+    }
+
+    fn allocate(&mut self, bytes: u64, field_name: &str) -> Result<()> {
+        let total_bytes = self.total_bytes.saturating_add(bytes);
+        if total_bytes > self.limits.max_total_bytes {
+            return Err(Error::failed(format!(
+                "capnp_conv: `{field_name}` takes the read past its limit of {} bytes",
+                self.limits.max_total_bytes
+            )));
+        }
+        self.total_bytes = total_bytes;
+        Ok(())
+    }
+}
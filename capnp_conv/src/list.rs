@@ -0,0 +1,150 @@
+use std::{borrow::Borrow, mem::size_of};
+
+use capnp::{
+    primitive_list,
+    private::layout::PrimitiveElement,
+    raw, struct_list,
+    traits::{Owned, OwnedStruct},
+    Error, Result,
+};
+
+use crate::{list_len, Writable};
+
+/// Primitives that generated code reads and writes as whole lists, copying the elements in bulk
+/// where the list is stored as a plain array of them.
+///
+/// Single byte elements are copied in bulk both ways. Larger numbers are read in bulk from the
+/// little endian bytes of the list, which does not depend on the target or on alignment, and are
+/// written in bulk to the list as a slice on little endian targets, unless the `unaligned` feature
+/// is enabled. It enables capnp's `unaligned` feature, which has no slices of larger numbers, so
+/// it must be enabled here rather than on capnp.
+pub trait ListPrimitive: PrimitiveElement + Copy {
+    /// Appends the elements of `reader` to `list`.
+    fn read_list(reader: primitive_list::Reader<'_, Self>, list: &mut Vec<Self>) {
+        list.extend(reader.iter());
+    }
+
+    /// Sets the elements of `builder` to `items`, which has its length.
+    fn write_list(mut builder: primitive_list::Builder<'_, Self>, items: &[Self]) {
+        for (idx, item) in (0..).zip(items) {
+            builder.set(idx, *item);
+        }
+    }
+}
+
+/// Appends the elements of `reader` to `list`.
+pub fn read_primitive_list<T: ListPrimitive>(
+    reader: primitive_list::Reader<'_, T>,
+    list: &mut Vec<T>,
+) {
+    T::read_list(reader, list);
+}
+
+/// Sets the elements of `builder` to `items`, which has its length.
+pub fn write_primitive_list<T: ListPrimitive>(
+    builder: primitive_list::Builder<'_, T>,
+    items: &[T],
+) {
+    T::write_list(builder, items);
+}
+
+/// Writes `items` to the list of structs that `init` initializes with their count, without
+/// collecting them first. `field_name` is the capnp field of the list, for errors.
+///
+/// Fails if `items` yields a different number of items than its length, leaving the list
+/// partly written.
+pub fn write_struct_list<'a, T>(
+    items: impl IntoIterator<IntoIter = impl ExactSizeIterator<Item = impl Borrow<T>>>,
+    field_name: &str,
+    init: impl FnOnce(u32) -> struct_list::Builder<'a, T::OwnedType>,
+) -> Result<()>
+where
+    T: Writable + ?Sized,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Builder<'b>: Into<<T::OwnedType as Owned>::Builder<'b>>,
+{
+    let mut items = items.into_iter();
+    let len = list_len(items.len(), field_name)?;
+    let mut builder = init(len);
+    for idx in 0..len {
+        let Some(item) = items.next() else {
+            return Err(Error::failed(format!(
+                "capnp_conv: `{field_name}` items ended after {idx} of {len}"
+            )));
+        };
+        item.borrow()
+            .try_write(builder.reborrow().get(idx).into())?;
+    }
+    if items.next().is_some() {
+        return Err(Error::failed(format!(
+            "capnp_conv: `{field_name}` has more items than its length of {len}"
+        )));
+    }
+    Ok(())
+}
+
+/// Copies `items` to the list as a slice, which is `None` when the list holds structs in place of
+/// primitives, after a schema change
+fn write_slice<T: ListPrimitive>(mut builder: primitive_list::Builder<'_, T>, items: &[T]) {
+    if let Some(slice) = builder.as_slice() {
+        slice[..items.len()].copy_from_slice(items);
+    } else {
+        for (idx, item) in (0..).zip(items) {
+            builder.set(idx, *item);
+        }
+    }
+}
+
+/// `as_slice` is `None` when the list holds structs in place of primitives, after a schema change
+macro_rules! impl_byte_list_primitive {
+    ($($ty:ty),*) => {$(
+        impl ListPrimitive for $ty {
+            fn read_list(reader: primitive_list::Reader<'_, Self>, list: &mut Vec<Self>) {
+                match reader.as_slice().and_then(|slice| slice.get(..reader.len() as usize)) {
+                    Some(slice) => list.extend_from_slice(slice),
+                    None => list.extend(reader.iter()),
+                }
+            }
+
+            fn write_list(builder: primitive_list::Builder<'_, Self>, items: &[Self]) {
+                write_slice(builder, items);
+            }
+        }
+    )*};
+}
+
+/// Reads from the bytes of the list, which are only those of the elements when it holds them
+/// rather than structs, after a schema change. Writes to the list as a slice where capnp has one,
+/// like lists of bytes
+macro_rules! impl_bulk_list_primitive {
+    ($($ty:ty),*) => {$(
+        impl ListPrimitive for $ty {
+            fn read_list(reader: primitive_list::Reader<'_, Self>, list: &mut Vec<Self>) {
+                if raw::get_list_element_size(reader) == Self::element_size() {
+                    let bytes = raw::get_list_bytes(reader);
+                    list.extend(bytes.chunks_exact(size_of::<Self>()).map(|chunk| {
+                        Self::from_le_bytes(chunk.try_into().expect("chunks have the element size"))
+                    }));
+                } else {
+                    list.extend(reader.iter());
+                }
+            }
+
+            #[cfg(all(target_endian = "little", not(feature = "unaligned")))]
+            fn write_list(builder: primitive_list::Builder<'_, Self>, items: &[Self]) {
+                write_slice(builder, items);
+            }
+        }
+    )*};
+}
+
+/// Primitives copied one element at a time
+macro_rules! impl_list_primitive {
+    ($($ty:ty),*) => {$(
+        impl ListPrimitive for $ty {}
+    )*};
+}
+
+impl_byte_list_primitive!(u8, i8);
+impl_bulk_list_primitive!(u16, i16, u32, i32, u64, i64, f32, f64);
+impl_list_primitive!(bool);
@@ -0,0 +1,147 @@
+use std::{
+    fmt,
+    ops::Deref,
+    sync::{Arc, OnceLock},
+};
+
+use capnp::{
+    message::{self, ReaderOptions, TypedReader},
+    serialize::{BufferSegments, NoAllocSliceSegments},
+    Error, Result, Word,
+};
+
+use crate::{
+    bytes::{to_words, ReadResult},
+    read_parallel, MessageOptions, ReadError, Readable, Writable,
+};
+
+/// An encoded message holding a `T`, which is decoded the first time it is needed and kept.
+///
+/// The message is stored in the standard encoding, so a `Message<T>` is `Send` and `Sync` when `T`
+/// is, and can be read with [`Message::reader`] without decoding it. Clones share the message.
+#[derive(Clone)]
+pub struct Message<T> {
+    words: SharedWords,
+    reader_options: ReaderOptions,
+    value: OnceLock<T>,
+}
+
+impl<T: Writable> Message<T> {
+    /// Encodes `value`, which is decoded again when it is needed.
+    pub fn new(value: &T) -> Result<Self> {
+        Message::new_with_options(value, &MessageOptions::default())
+    }
+
+    /// Encodes `value` with `options`, which are also used to read the message.
+    pub fn new_with_options(value: &T, options: &MessageOptions) -> Result<Self> {
+        Ok(Message {
+            words: SharedWords(Arc::new(to_words(&options.write_bytes(value)?))),
+            reader_options: options.reader_options,
+            value: OnceLock::new(),
+        })
+    }
+}
+
+impl<T: Writable + Readable> Message<T> {
+    /// Encodes `value` and decodes the message right away, keeping the decoded value.
+    ///
+    /// The value kept is read back from the message rather than `value` itself, so it is the one
+    /// [`Message::get`] would decode, with the `before_write` and `after_read` hooks applied.
+    pub fn from_value(value: T) -> core::result::Result<Self, ReadError<T::Error>> {
+        let message = Message::new(&value)?;
+        // The value is freed before the message is decoded to take its place
+        drop(value);
+        message.get()?;
+        Ok(message)
+    }
+}
+
+impl<T> Message<T> {
+    /// Copies a message in the standard encoding from the start of `bytes`, checking its segment
+    /// table.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Message::from_bytes_with_options(bytes, &MessageOptions::default())
+    }
+
+    /// Copies a message in the standard encoding from the start of `bytes`, to be read with
+    /// `options`.
+    pub fn from_bytes_with_options(bytes: &[u8], options: &MessageOptions) -> Result<Self> {
+        let mut words = to_words(bytes);
+        let mut rest = Word::words_to_bytes(&words);
+        NoAllocSliceSegments::from_slice(&mut rest, options.reader_options)?;
+        let len = words.len() * 8 - rest.len();
+        if len > bytes.len() {
+            return Err(Error::failed(format!(
+                "capnp_conv: message of {len} bytes ends after {} bytes",
+                bytes.len()
+            )));
+        }
+        words.truncate(len / 8);
+        Ok(Message {
+            words: SharedWords(Arc::new(words)),
+            reader_options: options.reader_options,
+            value: OnceLock::new(),
+        })
+    }
+
+    /// The message in the standard encoding.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.words
+    }
+}
+
+impl<T: Readable> Message<T> {
+    /// Reads the message without decoding it, the root is read with [`TypedReader::get`].
+    pub fn reader(&self) -> Result<TypedReader<NoAllocSliceSegments<'_>, T::OwnedType>> {
+        let segments = NoAllocSliceSegments::from_slice(&mut self.as_bytes(), self.reader_options)?;
+        Ok(TypedReader::new(message::Reader::new(
+            segments,
+            self.reader_options,
+        )))
+    }
+
+    /// The decoded value, which is decoded by the first call.
+    ///
+    /// Errors are not kept, a call after a failed one decodes the message again.
+    pub fn get(&self) -> core::result::Result<&T, ReadError<T::Error>> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+        let value = self.decode()?;
+        Ok(self.value.get_or_init(|| value))
+    }
+
+    /// The decoded value, decoding it if it has not been.
+    pub fn into_value(mut self) -> ReadResult<T> {
+        match self.value.take() {
+            Some(value) => Ok(value),
+            None => self.decode(),
+        }
+    }
+
+    fn decode(&self) -> ReadResult<T> {
+        let segments = BufferSegments::new(self.words.clone(), self.reader_options)?;
+        read_parallel(segments, self.reader_options)
+    }
+}
+
+/// The words of a message, which the threads of [`read_parallel`] share
+#[derive(Clone)]
+struct SharedWords(Arc<Vec<Word>>);
+
+impl Deref for SharedWords {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        Word::words_to_bytes(&self.0)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Message<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Message")
+            .field("len", &self.as_bytes().len())
+            .field("value", &self.value.get())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,313 @@
+use std::ops::Range;
+
+#[cfg(not(feature = "rayon"))]
+use capnp::message;
+use capnp::{
+    message::{ReaderOptions, ReaderSegments},
+    struct_list,
+    traits::{Owned, OwnedStruct},
+};
+
+use crate::{
+    bytes::{read_root, ReadResult},
+    ReadError, Readable,
+};
+
+type ListResult<T> = core::result::Result<Vec<T>, ReadError<<T as Readable>::Error>>;
+
+/// The fewest items of a `#[capnp_conv(parallel)]` list that are read in parallel, shorter lists
+/// are read like any other.
+pub const PARALLEL_MIN_LEN: u32 = 1024;
+
+/// Reads the root of the message in `segments`, reading the `#[capnp_conv(parallel)]` lists in it
+/// in parallel when the `rayon` feature is enabled.
+///
+/// The threads share the segments, which is why they are taken by value and must be `Send` and
+/// `Sync`, as the [`OwnedSegments`](capnp::serialize::OwnedSegments) of
+/// [`read_message`](capnp::serialize::read_message) are. Other reads read `parallel` lists in
+/// order.
+pub fn read_parallel<T, S>(segments: S, options: ReaderOptions) -> ReadResult<T>
+where
+    T: Readable,
+    S: ReaderSegments + Send + Sync + 'static,
+{
+    #[cfg(feature = "rayon")]
+    {
+        let message = rayon_read::SharedMessage::new(segments, options);
+        message.clone().with_shared(|| read_root(&message.reader()))
+    }
+    #[cfg(not(feature = "rayon"))]
+    read_root(&message::Reader::new(segments, options))
+}
+
+/// Reads the list of structs `reader`, used by `#[capnp_conv(parallel)]` fields.
+///
+/// With the `rayon` feature, lists of at least `min_len` items in a message read by
+/// [`read_parallel`] are split into chunks, which are read by the threads of the rayon pool. Items
+/// are in order, and the error is that of the first item that fails to read, as when reading
+/// them in order.
+pub fn read_list_parallel<T>(
+    reader: struct_list::Reader<'_, T::OwnedType>,
+    min_len: u32,
+) -> ListResult<T>
+where
+    T: Readable + Send,
+    T::Error: Send,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>: Into<<T::OwnedType as Owned>::Reader<'b>>,
+{
+    #[cfg(feature = "rayon")]
+    if reader.len() >= min_len {
+        if let Some(list) = rayon_read::SharedList::find(reader) {
+            return rayon_read::read_chunks(&list);
+        }
+    }
+    let _ = min_len;
+    read_items(reader, 0..reader.len())
+}
+
+fn read_items<T>(
+    reader: struct_list::Reader<'_, T::OwnedType>,
+    indices: Range<u32>,
+) -> ListResult<T>
+where
+    T: Readable,
+    T::OwnedType: OwnedStruct,
+    for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>: Into<<T::OwnedType as Owned>::Reader<'b>>,
+{
+    let mut list = Vec::with_capacity(indices.len());
+    for idx in indices {
+        let item =
+            T::read_with_path(reader.get(idx).into()).map_err(|error| error.at_index(idx))?;
+        list.push(item);
+    }
+    Ok(list)
+}
+
+#[cfg(feature = "rayon")]
+mod rayon_read {
+    use std::{cell::RefCell, sync::Arc};
+
+    use capnp::{
+        any_pointer,
+        message::{self, ReaderOptions, ReaderSegments},
+        private::{
+            arena::{ReaderArena, ReaderArenaImpl},
+            layout::PointerReader,
+        },
+        struct_list,
+        traits::{IntoInternalListReader, Owned, OwnedStruct},
+        Result, Word,
+    };
+    use rayon::prelude::*;
+
+    use super::{read_items, ListResult};
+    use crate::{ReadError, Readable};
+
+    /// Chunks per thread, so threads that finish early take on the chunks of slower ones
+    const CHUNKS_PER_THREAD: usize = 4;
+
+    thread_local! {
+        /// The message `read_parallel` is reading on this thread
+        static SHARED_MESSAGE: RefCell<Option<SharedMessage>> = const { RefCell::new(None) };
+    }
+
+    /// The segments of a message read by `read_parallel`, which the threads share
+    #[derive(Clone)]
+    pub(super) struct SharedMessage {
+        segments: Arc<dyn ReaderSegments + Send + Sync>,
+        options: ReaderOptions,
+    }
+
+    impl SharedMessage {
+        pub(super) fn new(
+            segments: impl ReaderSegments + Send + Sync + 'static,
+            options: ReaderOptions,
+        ) -> Self {
+            SharedMessage {
+                segments: Arc::new(segments),
+                options,
+            }
+        }
+
+        pub(super) fn reader(&self) -> message::Reader<Self> {
+            message::Reader::new(self.clone(), self.options)
+        }
+
+        /// Runs `read` with this message as the shared message of this thread
+        pub(super) fn with_shared<R>(self, read: impl FnOnce() -> R) -> R {
+            /// Puts back the shared message of the enclosing read, even if `read` panics
+            struct Restore(Option<SharedMessage>);
+
+            impl Drop for Restore {
+                fn drop(&mut self) {
+                    SHARED_MESSAGE.with(|shared| *shared.borrow_mut() = self.0.take());
+                }
+            }
+
+            let _restore = Restore(SHARED_MESSAGE.with(|shared| shared.replace(Some(self))));
+            read()
+        }
+    }
+
+    impl ReaderSegments for SharedMessage {
+        fn get_segment(&self, id: u32) -> Option<&[u8]> {
+            self.segments.get_segment(id)
+        }
+
+        fn len(&self) -> usize {
+            self.segments.len()
+        }
+    }
+
+    /// A list of structs in the segments of a shared message, which each thread reads from a
+    /// message of its own
+    pub(super) struct SharedList {
+        message: SharedMessage,
+        /// The far pointer to the list and the list pointer of a landing pad for it
+        landing_pad: [u64; 2],
+        len: u32,
+    }
+
+    impl SharedList {
+        /// Finds `reader` in the segments of the message `read_parallel` is reading on this
+        /// thread.
+        ///
+        /// Items are found by the address of their data section, so lists of structs with no
+        /// data, and lists of structs upgraded from lists of primitives, are not found.
+        pub(super) fn find<T: OwnedStruct>(reader: struct_list::Reader<'_, T>) -> Option<Self> {
+            let message = SHARED_MESSAGE.with(|shared| shared.borrow().clone())?;
+            let len = reader.len();
+            if len == 0 {
+                return None;
+            }
+            let item = |list: struct_list::Reader<'_, T>, idx| {
+                let item = list.into_internal_list_reader().get_struct_element(idx);
+                item.get_data_section_as_blob().as_ptr()
+            };
+            let first = reader.into_internal_list_reader().get_struct_element(0);
+            let data_words = (first.get_data_section_as_blob().len() / 8) as u64;
+            let pointers = u64::from(first.get_pointer_section_size());
+            if data_words == 0 {
+                return None;
+            }
+
+            let start = item(reader, 0);
+            let (segment_id, segment) = (0..u32::try_from(message.len()).ok()?).find_map(|id| {
+                let segment = message.get_segment(id)?;
+                segment
+                    .as_ptr_range()
+                    .contains(&start)
+                    .then_some((id, segment))
+            })?;
+            let offset = start as usize - segment.as_ptr() as usize;
+            let tag_offset = (offset / 8)
+                .checked_sub(1)
+                .filter(|_| offset.is_multiple_of(8))?;
+            let tag = segment.get(tag_offset * 8..offset)?;
+            let tag = u64::from_le_bytes(tag.try_into().ok()?);
+            // The list starts with the tag word of an inline composite list of its items
+            if tag != u64::from(len) << 2 | data_words << 32 | pointers << 48 {
+                return None;
+            }
+            let words = u64::from(len) * (data_words + pointers);
+            let tag_offset = u64::try_from(tag_offset).ok().filter(|&o| o < 1 << 29)?;
+            if words >= 1 << 29 {
+                return None;
+            }
+
+            let list = SharedList {
+                message,
+                landing_pad: [
+                    tag_offset << 3 | 2 | u64::from(segment_id) << 32,
+                    1 | (words << 3 | 7) << 32,
+                ],
+                len,
+            };
+            // The list each thread reads is the one that was found
+            let found = list.read(|shared: struct_list::Reader<'_, T>| {
+                shared.len() == len
+                    && item(shared, 0) == start
+                    && item(shared, len - 1) == item(reader, len - 1)
+            });
+            found.is_ok_and(|found| found).then_some(list)
+        }
+
+        /// Runs `read` with the list, read from a message of its own over the shared segments
+        /// and a landing pad for the list
+        fn read<T: OwnedStruct, R>(
+            &self,
+            read: impl FnOnce(struct_list::Reader<'_, T>) -> R,
+        ) -> Result<R> {
+            let pad_id = u32::try_from(self.message.len()).unwrap_or(u32::MAX);
+            // A double far pointer to the landing pad, which follows it
+            let root = 1 << 3 | 1 << 2 | 2 | u64::from(pad_id) << 32;
+            let segments = PaddedSegments {
+                message: &self.message,
+                pad: [root, self.landing_pad[0], self.landing_pad[1]].map(word),
+            };
+            let arena = ReaderArenaImpl::new(segments, self.message.options);
+            let (start, _) = arena.get_segment(pad_id)?;
+            let pointer = PointerReader::get_root(&arena, pad_id, start, arena.nesting_limit())?;
+            Ok(read(any_pointer::Reader::new(pointer).get_as()?))
+        }
+    }
+
+    /// The segments of a shared message, followed by a segment of pointers to a list in them
+    struct PaddedSegments<'a> {
+        message: &'a SharedMessage,
+        pad: [Word; 3],
+    }
+
+    impl ReaderSegments for PaddedSegments<'_> {
+        fn get_segment(&self, id: u32) -> Option<&[u8]> {
+            if id as usize == self.message.len() {
+                Some(Word::words_to_bytes(&self.pad))
+            } else {
+                self.message.get_segment(id)
+            }
+        }
+
+        fn len(&self) -> usize {
+            self.message.len() + 1
+        }
+    }
+
+    fn word(value: u64) -> Word {
+        let [b0, b1, b2, b3, b4, b5, b6, b7] = value.to_le_bytes();
+        capnp::word(b0, b1, b2, b3, b4, b5, b6, b7)
+    }
+
+    pub(super) fn read_chunks<T>(list: &SharedList) -> ListResult<T>
+    where
+        T: Readable + Send,
+        T::Error: Send,
+        T::OwnedType: OwnedStruct,
+        for<'b> <T::OwnedType as OwnedStruct>::Reader<'b>:
+            Into<<T::OwnedType as Owned>::Reader<'b>>,
+    {
+        let len = list.len;
+        let chunks = u32::try_from(rayon::current_num_threads() * CHUNKS_PER_THREAD)
+            .unwrap_or(u32::MAX)
+            .clamp(1, len);
+        let chunk_len = len.div_ceil(chunks);
+        let results: Vec<ListResult<T>> = (0..chunks)
+            .into_par_iter()
+            .map(|chunk| {
+                let start = chunk * chunk_len;
+                let indices = start.min(len)..(start + chunk_len).min(len);
+                // Parallel lists nested in the items are read in parallel as well
+                list.message.clone().with_shared(|| {
+                    list.read(|reader| read_items::<T>(reader, indices))
+                        .unwrap_or_else(|error| Err(ReadError::from(error)))
+                })
+            })
+            .collect();
+
+        let mut list = Vec::with_capacity(len as usize);
+        for items in results {
+            list.extend(items?);
+        }
+        Ok(list)
+    }
+}
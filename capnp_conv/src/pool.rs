@@ -0,0 +1,158 @@
+use std::{
+    io::Write,
+    ptr,
+    sync::{Mutex, PoisonError},
+};
+
+use capnp::{
+    message::{self, Allocator},
+    serialize, Result, Word,
+};
+
+use crate::{
+    bytes::{write_root, MAX_SEGMENT_WORDS},
+    MessageOptions, Writable,
+};
+
+/// Segments for message builders that are kept when the builders are dropped, and reused by later
+/// builders instead of allocating new ones.
+///
+/// Segments that would take the pool over its maximum size are freed instead.
+///
+/// The pool is `Sync`, so builders on several threads can share it:
+///
+/// ```
+/// let pool = capnp_conv::BuilderPool::default();
+/// let mut message = pool.builder();
+/// // The segments of `message` go back to the pool when it is dropped
+/// ```
+#[derive(Debug)]
+pub struct BuilderPool {
+    options: MessageOptions,
+    max_pooled_words: usize,
+    segments: Mutex<Vec<Box<[Word]>>>,
+}
+
+impl Default for BuilderPool {
+    fn default() -> Self {
+        BuilderPool::new(
+            MessageOptions::default(),
+            BuilderPool::DEFAULT_MAX_POOLED_WORDS,
+        )
+    }
+}
+
+impl BuilderPool {
+    /// The most words the default pool keeps, 8 MiB.
+    pub const DEFAULT_MAX_POOLED_WORDS: usize = 1 << 20;
+
+    /// An empty pool that keeps up to `max_pooled_words` words of segments, new segments start
+    /// from `options.first_segment_words`.
+    #[must_use]
+    pub fn new(options: MessageOptions, max_pooled_words: usize) -> Self {
+        BuilderPool {
+            options,
+            max_pooled_words,
+            segments: Mutex::default(),
+        }
+    }
+
+    /// A new message that allocates its segments from the pool.
+    pub fn builder(&self) -> message::Builder<PooledAllocator<'_>> {
+        message::Builder::new(PooledAllocator {
+            pool: self,
+            next_size: self.options.first_segment_words,
+        })
+    }
+
+    /// Writes `value` to the root of a new message that allocates its segments from the pool.
+    pub fn write_message<T: Writable + ?Sized>(
+        &self,
+        value: &T,
+    ) -> Result<message::Builder<PooledAllocator<'_>>> {
+        write_root(self.builder(), value)
+    }
+
+    /// Writes `value` as a message in the standard encoding, with a segment table.
+    pub fn write_bytes<T: Writable + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        Ok(serialize::write_message_to_words(
+            &self.write_message(value)?,
+        ))
+    }
+
+    /// Writes `value` to `write` as a message in the standard encoding.
+    pub fn write_to<T: Writable + ?Sized>(&self, value: &T, write: impl Write) -> Result<()> {
+        serialize::write_message(write, &self.write_message(value)?)
+    }
+
+    /// The number of segments waiting to be reused.
+    pub fn pooled_segments(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// The words of the segments waiting to be reused.
+    pub fn pooled_words(&self) -> usize {
+        self.lock().iter().map(|segment| segment.len()).sum()
+    }
+
+    /// Frees the segments waiting to be reused.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Box<[Word]>>> {
+        // Segments are zeroed before they are pooled, so a panic cannot leave a bad one behind
+        self.segments.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The largest pooled segment of at least `minimum_size` words.
+    fn take(&self, minimum_size: u32) -> Option<Box<[Word]>> {
+        let mut segments = self.lock();
+        let index = (0..segments.len())
+            .filter(|&index| segments[index].len() >= minimum_size as usize)
+            .max_by_key(|&index| segments[index].len())?;
+        Some(segments.swap_remove(index))
+    }
+
+    /// Pools `segment`, unless it would take the pool over `max_pooled_words`.
+    fn give(&self, segment: Box<[Word]>) {
+        let mut segments = self.lock();
+        let pooled_words: usize = segments.iter().map(|segment| segment.len()).sum();
+        if pooled_words + segment.len() <= self.max_pooled_words {
+            segments.push(segment);
+        }
+    }
+}
+
+/// The allocator of the builders of a [`BuilderPool`], which takes segments from the pool and
+/// returns them to it zeroed.
+pub struct PooledAllocator<'a> {
+    pool: &'a BuilderPool,
+    next_size: u32,
+}
+
+unsafe impl Allocator for PooledAllocator<'_> {
+    fn allocate_segment(&mut self, minimum_size: u32) -> (*mut u8, u32) {
+        let segment = self.pool.take(minimum_size).unwrap_or_else(|| {
+            let size = minimum_size.max(self.next_size);
+            self.next_size = self.next_size.saturating_add(size).min(MAX_SEGMENT_WORDS);
+            Word::allocate_zeroed_vec(size as usize).into_boxed_slice()
+        });
+        let size = u32::try_from(segment.len()).expect("segments are allocated with u32 sizes");
+        (Box::into_raw(segment).cast::<u8>(), size)
+    }
+
+    unsafe fn deallocate_segment(&mut self, ptr: *mut u8, word_size: u32, words_used: u32) {
+        // Safety: `ptr` and `word_size` come from a boxed segment in `allocate_segment`, so `ptr`
+        // is aligned for `Word`
+        #[allow(clippy::cast_ptr_alignment)]
+        let mut segment = unsafe {
+            Box::from_raw(ptr::slice_from_raw_parts_mut(
+                ptr.cast::<Word>(),
+                word_size as usize,
+            ))
+        };
+        Word::words_to_bytes_mut(&mut segment[..words_used as usize]).fill(0);
+        self.pool.give(segment);
+    }
+}
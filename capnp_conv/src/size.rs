@@ -0,0 +1,28 @@
+//! The sizes in words of encoded values, summed up by
+//! [`Writable::encoded_size_words`](crate::Writable::encoded_size_words).
+
+use capnp::traits::HasStructSize;
+
+/// The data and pointer sections of the struct built by `B`.
+#[must_use]
+pub fn struct_words<B: HasStructSize>() -> u64 {
+    u64::from(B::STRUCT_SIZE.data) + u64::from(B::STRUCT_SIZE.pointers)
+}
+
+/// A `Text` of `len` bytes, with its NUL terminator.
+#[must_use]
+pub fn text_words(len: usize) -> u64 {
+    (len as u64 + 1).div_ceil(8)
+}
+
+/// A `Data` of `len` bytes.
+#[must_use]
+pub fn data_words(len: usize) -> u64 {
+    (len as u64).div_ceil(8)
+}
+
+/// A list of `len` elements of `bits` each, for lists of primitives and enums.
+#[must_use]
+pub fn list_words(len: usize, bits: u64) -> u64 {
+    (len as u64 * bits).div_ceil(64)
+}
@@ -0,0 +1,81 @@
+use core::marker::PhantomData;
+
+use capnp::{
+    dynamic_struct, dynamic_value,
+    introspect::{Introspect, Type},
+    private::layout::{PointerBuilder, StructBuilder, StructReader, StructSize},
+    schema_capnp::node,
+    traits::{HasStructSize, IntoInternalStructReader, OwnedStruct, SetterInput},
+    Error, Result,
+};
+
+/// Sets the union discriminant of `builder`, a struct of the type `T`, to `discriminant`.
+///
+/// The discriminant is written as is at the offset of the union in the local schema, so
+/// discriminants of members that are missing from it are written back unchanged.
+pub fn set_union_discriminant<T>(builder: T::Builder<'_>, discriminant: u16) -> Result<()>
+where
+    T: OwnedStruct + 'static,
+    for<'a> T::Builder<'a>: Into<dynamic_value::Builder<'a>>,
+{
+    let builder = builder.into().downcast::<dynamic_struct::Builder>();
+    let node::Struct(schema) = builder.get_schema().get_proto().which()? else {
+        return Err(Error::failed(String::from(
+            "capnp_conv: union discriminants can only be set in structs",
+        )));
+    };
+    let offset = schema.get_discriminant_offset() as usize;
+    let RawBuilder(builder, _) = builder.downcast::<RawStruct<T>>();
+    builder.set_data_field::<u16>(offset, discriminant);
+    Ok(())
+}
+
+/// The struct type `T`, with readers and builders of its raw layout
+struct RawStruct<T>(PhantomData<T>);
+
+impl<T: Introspect> Introspect for RawStruct<T> {
+    fn introspect() -> Type {
+        T::introspect()
+    }
+}
+
+impl<T: OwnedStruct + 'static> OwnedStruct for RawStruct<T> {
+    type Reader<'a> = RawReader<'a>;
+    type Builder<'a> = RawBuilder<'a, T>;
+}
+
+struct RawReader<'a>(StructReader<'a>);
+
+impl<'a> From<StructReader<'a>> for RawReader<'a> {
+    fn from(reader: StructReader<'a>) -> Self {
+        RawReader(reader)
+    }
+}
+
+impl<'a> IntoInternalStructReader<'a> for RawReader<'a> {
+    fn into_internal_struct_reader(self) -> StructReader<'a> {
+        self.0
+    }
+}
+
+impl<T> SetterInput<RawStruct<T>> for RawReader<'_> {
+    fn set_pointer_builder(
+        mut builder: PointerBuilder<'_>,
+        input: Self,
+        canonicalize: bool,
+    ) -> Result<()> {
+        builder.set_struct(&input.0, canonicalize)
+    }
+}
+
+struct RawBuilder<'a, T>(StructBuilder<'a>, PhantomData<T>);
+
+impl<'a, T> From<StructBuilder<'a>> for RawBuilder<'a, T> {
+    fn from(builder: StructBuilder<'a>) -> Self {
+        RawBuilder(builder, PhantomData)
+    }
+}
+
+impl<T: OwnedStruct + 'static> HasStructSize for RawBuilder<'_, T> {
+    const STRUCT_SIZE: StructSize = <T::Builder<'static> as HasStructSize>::STRUCT_SIZE;
+}
@@ -0,0 +1,9 @@
+//! Builds of code generated without the features it needs, which are only off when this crate is
+//! tested on its own with `cargo test -p capnp_conv --no-default-features`, as CI does, since the
+//! tests crate enables them.
+#![cfg(not(feature = "bitflags"))]
+
+#[test]
+fn compile_fail_test() {
+    trybuild::TestCases::new().compile_fail("tests/compile_fail/*.rs");
+}
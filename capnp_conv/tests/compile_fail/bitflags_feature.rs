@@ -0,0 +1,27 @@
+use capnp_conv::capnp_conv;
+
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+#[path = "spans/spans_capnp.rs"]
+pub mod spans_capnp;
+
+// The generated code refers to its module by its path from the tests directory
+mod compile_fail {
+    pub mod spans {
+        pub use crate::spans_capnp;
+    }
+}
+
+use compile_fail::spans::spans_capnp::{point, Kind};
+
+pub struct Flags(i32);
+
+#[capnp_conv(point)]
+pub struct Point {
+    #[capnp_conv(type = "bitflags")]
+    pub x: Flags,
+    #[capnp_conv(type = "enum")]
+    pub kind: Kind,
+}
+
+fn main() {}
@@ -1,88 +1,410 @@
-use heck::{ToSnakeCase, ToUpperCamelCase};
-use proc_macro2::TokenStream as TokenStream2;
-use quote::{format_ident, quote, ToTokens};
-use syn::{Ident, Path};
+use heck::{ToLowerCamelCase, ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{spanned::Spanned, Expr, Ident, Path};
 
 use crate::{
-    models::{EnumInfo, FieldInfo, FieldType, ItemInfo, StructInfo},
-    utils::{as_turbofish, capitalize_first_letter, is_ptr_type, to_capnp_generic, to_ident},
+    models::{
+        CodedEnumInfo, EnumCoding, EnumInfo, FieldInfo, FieldType, Hooks, Int128Encoding, ItemInfo,
+        ItemOptions, ItemTarget, StructInfo, UndefinedBits,
+    },
+    utils::{
+        as_turbofish, capitalize_first_letter, is_ptr_type, mentions_any, respan, to_capnp_generic,
+    },
 };
 
+/// How generated readers handle errors
+#[derive(Clone, Copy)]
+struct ReadMode {
+    /// The item has an `error` type, which `validate` errors convert to
+    typed_errors: bool,
+    /// Reading for `read_lenient`, fields that fail to read are replaced with their default and
+    /// their errors are pushed to `lenient_errors`
+    lenient: bool,
+    /// Reading for `read_in_context`, allocations are checked against the `read_context` limits
+    limited: bool,
+}
+
+/// Where a generated read is within the item being read, for the path of its errors
+#[derive(Clone, Default)]
+struct ReadPosition {
+    /// Calls of the `ReadError` methods that add the path of the read, innermost first
+    segments: Vec<TokenStream2>,
+    /// How many lists the read is in, which names the index of each
+    list_depth: usize,
+    /// In `read_lenient`, the default of the field the read is in, which replaces the field when
+    /// the read fails
+    lenient_default: Option<TokenStream2>,
+    /// The item has an `error` type, which errors of nested reads convert to
+    typed_errors: bool,
+}
+
+impl ReadPosition {
+    /// A read of the field or union variant `rust_name`, whose capnp name is `capnp_name`
+    fn field(
+        rust_name: &str,
+        capnp_name: &str,
+        lenient_default: Option<TokenStream2>,
+        typed_errors: bool,
+    ) -> Self {
+        ReadPosition {
+            segments: vec![quote!(in_field(#rust_name, #capnp_name))],
+            list_depth: 0,
+            lenient_default,
+            typed_errors,
+        }
+    }
+
+    /// A read of an item of the list read at this position
+    fn list_item(&self) -> Self {
+        let mut item = ReadPosition {
+            segments: Vec::with_capacity(self.segments.len() + 1),
+            list_depth: self.list_depth + 1,
+            lenient_default: self.lenient_default.clone(),
+            typed_errors: self.typed_errors,
+        };
+        let index = item.index();
+        item.segments.push(quote!(at_index(#index)));
+        item.segments.extend(self.segments.iter().cloned());
+        item
+    }
+
+    /// The index of the innermost list item the read is in
+    fn index(&self) -> Ident {
+        format_ident!("idx{}", self.list_depth)
+    }
+
+    /// Adds the path of the read to `error`, a `ReadError` of the item
+    fn generate_error(&self, error: impl ToTokens) -> TokenStream2 {
+        let segments = &self.segments;
+        quote!(#error #(.#segments)*)
+    }
+
+    /// Unwraps `result`, whose `error` is converted to a `ReadError` of the item by
+    /// `to_read_error`
+    ///
+    /// In `read_lenient`, a failed read pushes its error to `lenient_errors` and the field it is in
+    /// is replaced with its default
+    fn generate_unwrap(&self, result: impl ToTokens, to_read_error: impl ToTokens) -> TokenStream2 {
+        let error = self.generate_error(to_read_error);
+        if let Some(default) = &self.lenient_default {
+            quote! {
+              match #result {
+                Ok(value) => value,
+                Err(error) => {
+                  lenient_errors.push(#error);
+                  break 'field #default;
+                }
+              }
+            }
+        } else {
+            quote!(#result.map_err(|error| #error)?)
+        }
+    }
+
+    /// Unwraps `result`, whose error converts to a `ReadError` of the item with `From`
+    fn generate_try(&self, result: impl ToTokens) -> TokenStream2 {
+        if self.segments.is_empty() && self.lenient_default.is_none() {
+            return quote!(#result?);
+        }
+        let read_error = generate_read_error_type();
+        self.generate_unwrap(
+            result,
+            quote!(<#read_error as ::core::convert::From<_>>::from(error)),
+        )
+    }
+
+    /// Unwraps `result` of a nested read, converting its error to the error type of the item
+    fn generate_try_nested(&self, result: impl ToTokens) -> TokenStream2 {
+        self.generate_unwrap(result, self.generate_nested_error(quote!(error)))
+    }
+
+    /// Converts `error`, a `ReadError` of a nested read, to a `ReadError` of the item
+    ///
+    /// An `error` type of the item converts from that of the nested item with `From`, while
+    /// `capnp::Error` takes any error, so items with custom errors can be nested in any item
+    fn generate_nested_error(&self, error: impl ToTokens) -> TokenStream2 {
+        if self.typed_errors {
+            quote!(#error.convert::<<Self as ::capnp_conv::Readable>::Error>())
+        } else {
+            quote!(#error.into_capnp())
+        }
+    }
+
+    /// Fails the read with `error`, a `ReadError` of the item
+    fn generate_fail(&self, error: impl ToTokens) -> TokenStream2 {
+        let error = self.generate_error(error);
+        if let Some(default) = &self.lenient_default {
+            quote! {
+              {
+                lenient_errors.push(#error);
+                break 'field #default;
+              }
+            }
+        } else {
+            quote!(return Err(#error))
+        }
+    }
+}
+
+/// How generated writers handle values the schema cannot hold
+#[derive(Clone, Copy)]
+struct WriteMode {
+    /// Writing for `try_write`, such values are errors. `write` panics on values that do not fit,
+    /// truncates list lengths and lets the last union field set win
+    checked: bool,
+}
+
+impl WriteMode {
+    /// Sets `value`, a `Result` of a conversion that can fail, with `set`, `write` panics with its
+    /// error
+    fn generate_fallible_set(
+        self,
+        value: impl ToTokens,
+        set: impl FnOnce(TokenStream2) -> TokenStream2,
+    ) -> TokenStream2 {
+        if self.checked {
+            set(quote!(#value?))
+        } else {
+            set(quote!(::capnp_conv::expect_written(#value)))
+        }
+    }
+}
+
 impl ItemInfo {
-    pub fn generate_impls(&self, capnp_path: &Path) -> TokenStream2 {
-        let impls = match self {
-            ItemInfo::Struct(struct_info) => vec![
-                struct_info.generate_writer_impl(capnp_path),
-                struct_info.generate_reader_impl(capnp_path),
-                struct_info.generate_try_from_impl(capnp_path),
-            ],
-            ItemInfo::Enum(enum_info) if enum_info.is_union() => vec![
-                enum_info.generate_writer_impl(capnp_path),
-                enum_info.generate_reader_impl(capnp_path),
-                enum_info.generate_try_from_impl(capnp_path),
+    pub fn generate_impls(&self, target: &ItemTarget) -> TokenStream2 {
+        let impls = match (self, target) {
+            (ItemInfo::CodedEnum(enum_info), _) => vec![enum_info.generate_coded_impl()],
+            (_, ItemTarget::Capnp(capnp_path, options)) => {
+                self.generate_capnp_impls(capnp_path, options)
+            }
+            _ => unreachable!("only enums can be text or int enums"),
+        };
+        quote! {
+          #(
+            #[allow(clippy::all, clippy::pedantic, non_camel_case_types, unused_variables, unused_braces, unused_mut, unused_labels, unreachable_code)]
+            #impls
+          )*
+        }
+    }
+    fn generate_capnp_impls(&self, capnp_path: &Path, options: &ItemOptions) -> Vec<TokenStream2> {
+        match self {
+            ItemInfo::Struct(struct_info) => {
+                let mut items = vec![];
+                let mut impls = vec![
+                    struct_info.generate_writer_impl(capnp_path, &options.hooks),
+                    struct_info.generate_reader_impl(capnp_path, options),
+                    struct_info.generate_try_from_impl(capnp_path, options),
+                ];
+                if options.view {
+                    let [view_struct, view_impls] = struct_info.generate_view(capnp_path, options);
+                    items.push(view_struct);
+                    impls.push(view_impls);
+                }
+                items.push(generate_with_accessor_fallbacks(
+                    &struct_info.fields,
+                    capnp_path,
+                    &impls,
+                ));
+                items
+            }
+            ItemInfo::Enum(enum_info) if enum_info.is_union() => {
+                vec![generate_with_accessor_fallbacks(
+                    &enum_info.fields,
+                    capnp_path,
+                    &[
+                        enum_info.generate_writer_impl(capnp_path, &options.hooks),
+                        enum_info.generate_reader_impl(capnp_path, options),
+                        enum_info.generate_try_from_impl(capnp_path, options),
+                    ],
+                )]
+            }
+            ItemInfo::Enum(enum_info) if enum_info.unknown_variant.is_some() => vec![
+                enum_info.generate_from_impl(capnp_path),
+                enum_info.generate_try_from_ordinal_impl(capnp_path),
+                enum_info.generate_into_ordinal_impl(capnp_path),
+                enum_info.generate_into_owned_ordinal_impl(),
             ],
             ItemInfo::Enum(enum_info) => vec![
                 enum_info.generate_into_impl(capnp_path),
                 enum_info.generate_from_impl(capnp_path),
                 enum_info.generate_to_impl(capnp_path),
+                enum_info.generate_try_from_ordinal_impl(capnp_path),
+                enum_info.generate_into_ordinal_impl(capnp_path),
+                enum_info.generate_into_owned_ordinal_impl(),
             ],
-        };
-        quote! {
-          #(
-            #[allow(clippy::all, clippy::pedantic, non_camel_case_types, unused_variables, unused_braces, unused_mut)]
-            #impls
-          )*
+            ItemInfo::CodedEnum(_) => unreachable!("text and int enums have no capnp path"),
+        }
+    }
+}
+
+/// Puts `impls` in a scope with fallbacks for the capnp accessors of `fields`, which are only
+/// picked when the capnp type has no accessor of that name, so that a field the schema does not
+/// have fails with the capnp name it was expected to have rather than a missing method
+fn generate_with_accessor_fallbacks(
+    fields: &[FieldInfo],
+    capnp_path: &Path,
+    impls: &[TokenStream2],
+) -> TokenStream2 {
+    let capnp_type = capnp_path.to_token_stream().to_string().replace(' ', "");
+    let (markers, accessors): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .filter(|field| {
+            (!field.skip_read || !field.skip_write)
+                && !matches!(
+                    field.field_type,
+                    FieldType::Phantom | FieldType::UnnamedUnion(_)
+                )
+        })
+        .map(|field| field.generate_accessor_fallbacks(&capnp_type))
+        .unzip();
+    quote! {
+      const _: () = {
+        #(#markers)*
+
+        #[allow(dead_code)]
+        trait CapnpAccessors {
+          #(#accessors)*
         }
+
+        impl<T: ?Sized> CapnpAccessors for T {}
+
+        #(#impls)*
+      };
     }
 }
 
 impl StructInfo {
-    fn generate_writer_impl(&self, capnp_path: &Path) -> TokenStream2 {
-        let field_writers: Vec<TokenStream2> = self
-            .fields
-            .iter()
-            .map(|field| {
-                if field.is_union_field {
-                    let rust_field_name = &field.rust_name;
-                    let field_writer = field.generate_field_writer_from_reference();
-                    quote! {
-                      if let Some(val) = &self.#rust_field_name {
-                        #field_writer;
-                      }
+    fn generate_writer_impl(&self, capnp_path: &Path, hooks: &Hooks) -> TokenStream2 {
+        let generate_write_body = |mode: WriteMode| {
+            let field_writers: Vec<TokenStream2> = self
+                .fields
+                .iter()
+                .map(|field| {
+                    if field.is_union_field {
+                        let rust_field_name = &field.rust_name;
+                        let field_writer = field.generate_field_writer_from_reference(mode);
+                        quote! {
+                          if let Some(val) = &this.#rust_field_name {
+                            #field_writer;
+                          }
+                        }
+                    } else {
+                        field.generate_field_writer_from_owned(mode)
                     }
-                } else {
-                    field.generate_field_writer_from_owned()
+                })
+                .collect();
+
+            let other_writer = self.other_field.as_ref().map(|other_field| {
+                let union_writer = generate_union_discriminant_writer(quote!(*discriminant), mode);
+                quote! {
+                  if let Some(discriminant) = &this.#other_field {
+                    #union_writer;
+                  }
                 }
-            })
-            .collect();
+            });
 
-        let write_body = quote! {
-          #(#field_writers;)*
+            let union_check = self.generate_union_check().filter(|_| mode.checked);
+
+            quote! {
+              #union_check
+              #(#field_writers;)*
+              #other_writer
+            }
         };
 
-        generate_writable_impl(&self.ident, capnp_path, &self.generics, write_body)
+        let field_sizes = self.fields.iter().filter_map(|field| {
+            let rust_field_name = &field.rust_name;
+            field.generate_size_words(quote!(&this.#rust_field_name))
+        });
+        let size_body = quote!(section #(+ #field_sizes)*);
+
+        generate_writable_impl(
+            &self.ident,
+            capnp_path,
+            &self.generics,
+            hooks,
+            generate_write_body,
+            size_body,
+        )
+    }
+    /// Fails `try_write` when more than one union field is set, `None` if the struct has at most
+    /// one
+    fn generate_union_check(&self) -> Option<TokenStream2> {
+        let union_field_checks: Vec<TokenStream2> = self
+            .fields
+            .iter()
+            .filter(|field| field.is_union_field)
+            .map(|field| &field.rust_name)
+            .chain(&self.other_field)
+            .map(|name| {
+                let name_str = name.to_string();
+                quote!((#name_str, this.#name.is_some()))
+            })
+            .collect();
+        (union_field_checks.len() > 1).then(|| {
+            let type_name = self.ident.to_string();
+            quote!(::capnp_conv::check_union_fields(#type_name, &[#(#union_field_checks),*])?;)
+        })
+    }
+    fn generate_reader_impl(&self, capnp_path: &Path, options: &ItemOptions) -> TokenStream2 {
+        generate_readable_impl(
+            &self.ident,
+            capnp_path,
+            &self.generics,
+            options,
+            |mode| self.generate_reader_body(capnp_path, mode),
+            self.generate_read_into(options),
+        )
+    }
+    /// `read_into` that reads each field in place, `None` for items whose fields cannot be, which
+    /// use the default `read_into`
+    ///
+    /// Union fields are set together, and item hooks take the whole value
+    fn generate_read_into(&self, options: &ItemOptions) -> Option<TokenStream2> {
+        if self.other_field.is_some()
+            || self.fields.iter().any(|field| field.is_union_field)
+            || options.hooks.after_read.is_some()
+            || options.hooks.validate.is_some()
+        {
+            return None;
+        }
+        let mode = ReadMode {
+            typed_errors: options.error.is_some(),
+            lenient: false,
+            limited: false,
+        };
+        let field_readers = self
+            .fields
+            .iter()
+            .map(|field| field.generate_field_read_into(mode));
+        let body = quote!(#(#field_readers)*);
+        Some(generate_read_into_fn(&body, &self.ident.to_string()))
     }
-    fn generate_reader_impl(&self, capnp_path: &Path) -> TokenStream2 {
+    fn generate_reader_body(&self, capnp_path: &Path, mode: ReadMode) -> TokenStream2 {
         let (union_fields, non_union_fields): (Vec<&FieldInfo>, Vec<&FieldInfo>) =
             self.fields.iter().partition(|f| f.is_union_field);
 
         let (non_union_field_names, non_union_readers): (Vec<&Ident>, Vec<TokenStream2>) =
             non_union_fields
                 .iter()
-                .map(|field| (&field.rust_name, field.generate_field_reader(false)))
+                .map(|field| {
+                    let field_reader = field.generate_field_reader(false, mode, &self.generics);
+                    (&field.rust_name, field_reader)
+                })
                 .unzip();
 
         let match_arms: Vec<TokenStream2> = union_fields
             .iter()
             .map(|field| {
-                let variant_name =
-                    to_ident(field.get_capnp_name(ToUpperCamelCase::to_upper_camel_case));
+                let variant_name = field.get_capnp_ident(ToUpperCamelCase::to_upper_camel_case);
                 let union_field_readers: Vec<TokenStream2> = union_fields
                     .iter()
                     .map(|union_field| {
                         let field_name = &union_field.rust_name;
                         if &field.rust_name == field_name {
-                            let field_reader = union_field.generate_field_reader(true);
+                            let field_reader =
+                                union_field.generate_field_reader(true, mode, &self.generics);
                             quote!(#field_name: Some(#field_reader))
                         } else {
                             quote!(#field_name: None)
@@ -90,21 +412,45 @@ impl StructInfo {
                     })
                     .collect();
 
+                let other_field = self
+                    .other_field
+                    .as_ref()
+                    .map(|other_field| quote!(#other_field: None,));
+
+                let pattern = wrap_which_pattern(
+                    quote!(#capnp_path::Which::#variant_name(val)),
+                    self.other_field.is_some(),
+                );
+
                 quote! {
-                  #capnp_path::Which::#variant_name(val) => Self {
+                  #pattern => Self {
                     #(#non_union_field_names,)*
                     #(#union_field_readers,)*
+                    #other_field
                   }
                 }
             })
             .collect();
 
-        let reader_body = if match_arms.is_empty() {
+        if match_arms.is_empty() {
             quote! {
               Ok(Self {
                 #(#non_union_field_names: #non_union_readers,)*
               })
             }
+        } else if let Some(other_field) = &self.other_field {
+            let union_field_names = union_fields.iter().map(|field| &field.rust_name);
+            quote! {
+              #(let #non_union_field_names = #non_union_readers;)*
+              Ok(match reader.which() {
+                #(#match_arms,)*
+                Err(::capnp::NotInSchema(discriminant)) => Self {
+                  #(#non_union_field_names,)*
+                  #(#union_field_names: None,)*
+                  #other_field: Some(discriminant),
+                },
+              })
+            }
         } else {
             quote! {
               #(let #non_union_field_names = #non_union_readers;)*
@@ -112,12 +458,124 @@ impl StructInfo {
                 #(#match_arms,)*
               })
             }
+        }
+    }
+    fn generate_try_from_impl(&self, capnp_path: &Path, options: &ItemOptions) -> TokenStream2 {
+        generate_try_from_impl(&self.ident, capnp_path, &self.generics, options)
+    }
+    /// `{Item}View`, which wraps a reader and has a method reading each field, and its impls
+    ///
+    /// The fields are read in a private trait implemented for the item, so that `Self` in the
+    /// field readers is the item rather than the view
+    fn generate_view(&self, capnp_path: &Path, options: &ItemOptions) -> [TokenStream2; 2] {
+        let rust_name = &self.ident;
+        let vis = &self.vis;
+        let view_name = format_ident!("{}View", rust_name);
+        let type_name = rust_name.to_string();
+        let read_error = generate_read_error_type();
+        let item_read_error =
+            quote!(::capnp_conv::ReadError<<#rust_name as ::capnp_conv::Readable>::Error>);
+        let mode = ReadMode {
+            typed_errors: options.error.is_some(),
+            lenient: false,
+            limited: false,
         };
+        let has_other = self.other_field.is_some();
 
-        generate_readable_impl(&self.ident, capnp_path, &self.generics, reader_body)
-    }
-    fn generate_try_from_impl(&self, capnp_path: &Path) -> TokenStream2 {
-        generate_try_from_impl(&self.ident, capnp_path, &self.generics)
+        let mut field_names = Vec::new();
+        let mut return_types = Vec::new();
+        let mut field_readers = Vec::new();
+        for field in &self.fields {
+            if field.skip_read || matches!(field.field_type, FieldType::Phantom) {
+                continue;
+            }
+            let (return_type, field_reader) =
+                field.generate_view_reader(capnp_path, has_other, mode);
+            field_names.push(&field.rust_name);
+            return_types.push(return_type);
+            field_readers.push(field_reader);
+        }
+        if let Some(other_field) = &self.other_field {
+            field_names.push(other_field);
+            return_types.push(quote!(::core::option::Option<u16>));
+            field_readers.push(quote! {
+              match reader.which() {
+                Ok(_) => None,
+                Err(::capnp::NotInSchema(discriminant)) => Some(discriminant),
+              }
+            });
+        }
+        let field_docs = field_names
+            .iter()
+            .map(|name| format!("Reads `{name}` from the reader."));
+
+        let view_doc = format!(
+            "A view of a `{}` reader that reads the fields of [`{rust_name}`] when they are asked \
+            for.",
+            capnp_path.to_token_stream().to_string().replace(' ', "")
+        );
+        let view_struct = quote! {
+          #[doc = #view_doc]
+          #[derive(Clone, Copy, Debug)]
+          #vis struct #view_name<'a> {
+            reader: #capnp_path::Reader<'a>,
+          }
+        };
+        let view_impls = quote! {
+          const _: () = {
+            trait ViewFields: ::capnp_conv::Readable {
+              #(fn #field_names<'a>(
+                reader: #capnp_path::Reader<'a>
+              ) -> ::core::result::Result<#return_types, #read_error>;)*
+            }
+
+            impl ViewFields for #rust_name {
+              #(fn #field_names<'a>(
+                reader: #capnp_path::Reader<'a>
+              ) -> ::core::result::Result<#return_types, #read_error> {
+                let read = move || -> ::core::result::Result<#return_types, #read_error> {
+                  Ok(#field_readers)
+                };
+                read().map_err(|error| error.in_type(#type_name))
+              })*
+            }
+
+            impl<'a> #view_name<'a> {
+              /// A view of `reader`, which reads nothing until a field is asked for.
+              pub fn new(reader: #capnp_path::Reader<'a>) -> Self {
+                #view_name { reader }
+              }
+
+              /// The reader of the view.
+              pub fn reader(&self) -> #capnp_path::Reader<'a> {
+                self.reader
+              }
+
+              /// Reads all of the fields, like `Readable::read_with_path`.
+              pub fn read(&self) -> ::core::result::Result<#rust_name, #item_read_error> {
+                <#rust_name as ::capnp_conv::Readable>::read_with_path(self.reader)
+              }
+
+              #(
+                #[doc = #field_docs]
+                pub fn #field_names(&self) -> ::core::result::Result<#return_types, #item_read_error> {
+                  <#rust_name as ViewFields>::#field_names(self.reader)
+                }
+              )*
+            }
+
+            impl ::capnp_conv::Viewable for #rust_name {
+              type View<'a> = #view_name<'a>;
+
+              fn view(
+                reader: <Self::OwnedType as ::capnp::traits::Owned>::Reader<'_>
+              ) -> #view_name<'_> {
+                #view_name::new(reader)
+              }
+            }
+          };
+        };
+        [view_struct, view_impls]
     }
 }
 
@@ -126,7 +584,7 @@ impl EnumInfo {
         let rust_name = &self.ident;
         let match_arms = self.fields.iter().map(|field| {
             let rust_field_name = &field.rust_name;
-            let capnp_field_name = to_ident(field.get_capnp_name(capitalize_first_letter));
+            let capnp_field_name = field.get_capnp_ident(capitalize_first_letter);
 
             quote!(#rust_name::#rust_field_name => #capnp_path::#capnp_field_name)
         });
@@ -154,7 +612,7 @@ impl EnumInfo {
         let rust_name = &self.ident;
         let match_arms = self.fields.iter().map(|field| {
             let rust_field_name = &field.rust_name;
-            let capnp_field_name = to_ident(field.get_capnp_name(capitalize_first_letter));
+            let capnp_field_name = field.get_capnp_ident(capitalize_first_letter);
 
             quote!(#capnp_path::#capnp_field_name => #rust_name::#rust_field_name)
         });
@@ -168,63 +626,174 @@ impl EnumInfo {
           }
         }
     }
+    fn generate_try_from_ordinal_impl(&self, capnp_path: &Path) -> TokenStream2 {
+        let rust_name = &self.ident;
+        let unknown_ordinal = if let Some(unknown_variant) = &self.unknown_variant {
+            quote!(Ok(Self::#unknown_variant(ordinal)))
+        } else {
+            quote!(Err(::capnp::NotInSchema(ordinal)))
+        };
+        quote! {
+          impl ::core::convert::TryFrom<u16> for #rust_name {
+            type Error = ::capnp::NotInSchema;
 
-    fn generate_writer_impl(&self, capnp_path: &Path) -> TokenStream2 {
-        let match_arm_writers: Vec<TokenStream2> = self
-            .fields
-            .iter()
-            .map(|field| {
-                let rust_variant_name = &field.rust_name;
-                let field_writer = field.generate_field_writer_from_reference();
-                let tuple_fields = if field.has_phantom_in_variant {
-                    quote!(val, _)
-                } else {
-                    quote!(val)
-                };
+            fn try_from(ordinal: u16) -> ::core::result::Result<Self, ::capnp::NotInSchema> {
+              match <#capnp_path as ::core::convert::TryFrom<u16>>::try_from(ordinal) {
+                Ok(value) => Ok(value.into()),
+                Err(::capnp::NotInSchema(ordinal)) => #unknown_ordinal,
+              }
+            }
+          }
+        }
+    }
+    fn generate_into_ordinal_impl(&self, capnp_path: &Path) -> TokenStream2 {
+        let rust_name = &self.ident;
+        let match_arms = self.fields.iter().map(|field| {
+            let rust_field_name = &field.rust_name;
+            let capnp_field_name = field.get_capnp_ident(capitalize_first_letter);
 
-                quote!(Self::#rust_variant_name(#tuple_fields) => #field_writer)
-            })
-            .collect();
+            quote!(#rust_name::#rust_field_name => u16::from(#capnp_path::#capnp_field_name))
+        });
+        let unknown_arm = self
+            .unknown_variant
+            .as_ref()
+            .map(|unknown_variant| quote!(#rust_name::#unknown_variant(ordinal) => *ordinal,));
+        quote! {
+          impl ::core::convert::From<&#rust_name> for u16 {
+            fn from(value: &#rust_name) -> Self {
+              match value {
+                #(#match_arms,)*
+                #unknown_arm
+              }
+            }
+          }
+        }
+    }
+    fn generate_into_owned_ordinal_impl(&self) -> TokenStream2 {
+        let rust_name = &self.ident;
+        quote! {
+          impl ::core::convert::From<#rust_name> for u16 {
+            fn from(value: #rust_name) -> Self {
+              u16::from(&value)
+            }
+          }
+        }
+    }
+
+    fn generate_writer_impl(&self, capnp_path: &Path, hooks: &Hooks) -> TokenStream2 {
+        let generate_write_body = |mode: WriteMode| {
+            let match_arm_writers: Vec<TokenStream2> = self
+                .fields
+                .iter()
+                .map(|field| {
+                    let rust_variant_name = &field.rust_name;
+                    let field_writer = field.generate_field_writer_from_reference(mode);
+                    let tuple_fields = if field.has_phantom_in_variant {
+                        quote!(val, _)
+                    } else {
+                        quote!(val)
+                    };
+
+                    quote!(Self::#rust_variant_name(#tuple_fields) => #field_writer)
+                })
+                .collect();
+
+            let other_writer = self.other_variant.as_ref().map(|other_variant| {
+                let union_writer = generate_union_discriminant_writer(quote!(*discriminant), mode);
+                quote!(Self::#other_variant(discriminant) => #union_writer,)
+            });
+
+            quote! {
+              match this {
+                #(#match_arm_writers,)*
+                #other_writer
+              }
+            }
+        };
 
-        let write_body = quote! {
-          match self {
-            #(#match_arm_writers,)*
+        let match_arm_sizes = self.fields.iter().map(|field| {
+            let rust_variant_name = &field.rust_name;
+            let size = field
+                .generate_size_words(quote!(val))
+                .unwrap_or_else(|| quote!(0));
+            quote!(Self::#rust_variant_name(val, ..) => #size)
+        });
+        let other_size = self
+            .other_variant
+            .as_ref()
+            .map(|other_variant| quote!(Self::#other_variant(_) => 0,));
+        let size_body = quote! {
+          section + match this {
+            #(#match_arm_sizes,)*
+            #other_size
           }
         };
 
-        generate_writable_impl(&self.ident, capnp_path, &self.generics, write_body)
+        generate_writable_impl(
+            &self.ident,
+            capnp_path,
+            &self.generics,
+            hooks,
+            generate_write_body,
+            size_body,
+        )
+    }
+    fn generate_reader_impl(&self, capnp_path: &Path, options: &ItemOptions) -> TokenStream2 {
+        generate_readable_impl(
+            &self.ident,
+            capnp_path,
+            &self.generics,
+            options,
+            |mode| self.generate_reader_body(capnp_path, mode),
+            quote!(),
+        )
     }
-    fn generate_reader_impl(&self, capnp_path: &Path) -> TokenStream2 {
+    fn generate_reader_body(&self, capnp_path: &Path, mode: ReadMode) -> TokenStream2 {
         let match_arm_readers: Vec<TokenStream2> = self
       .fields
       .iter()
       .map(|field| {
         let rust_variant_name = &field.rust_name;
         let capnp_field_name = field.get_capnp_name(ToSnakeCase::to_snake_case);
-        let capnp_variant_name = to_ident(capnp_field_name.to_upper_camel_case());
-        let field_reader = field.generate_field_reader(true);
+        let capnp_variant_name = format_ident!(
+          "{}",
+          capnp_field_name.to_upper_camel_case(),
+          span = field.get_capnp_name_span()
+        );
+        let field_reader = field.generate_field_reader(true, mode, &self.generics);
         let variant_fields = if field.has_phantom_in_variant {
           quote!(#field_reader, ::std::marker::PhantomData)
         } else {
           field_reader
         };
+        let pattern = wrap_which_pattern(
+          quote!(#capnp_path::Which::#capnp_variant_name(val)),
+          self.other_variant.is_some(),
+        );
 
         quote! {
-          #capnp_path::Which::#capnp_variant_name(val) => Self::#rust_variant_name(#variant_fields)
+          #pattern => Self::#rust_variant_name(#variant_fields)
         }
       })
       .collect();
 
-        let reader_body = quote! {
-          Ok(match reader.which()? {
-            #(#match_arm_readers,)*
-          })
-        };
-
-        generate_readable_impl(&self.ident, capnp_path, &self.generics, reader_body)
+        if let Some(other_variant) = &self.other_variant {
+            quote! {
+              Ok(match reader.which() {
+                #(#match_arm_readers,)*
+                Err(::capnp::NotInSchema(discriminant)) => Self::#other_variant(discriminant),
+              })
+            }
+        } else {
+            quote! {
+              Ok(match reader.which()? {
+                #(#match_arm_readers,)*
+              })
+            }
+        }
     }
-    fn generate_try_from_impl(&self, capnp_path: &Path) -> TokenStream2 {
-        generate_try_from_impl(&self.ident, capnp_path, &self.generics)
+    fn generate_try_from_impl(&self, capnp_path: &Path, options: &ItemOptions) -> TokenStream2 {
+        generate_try_from_impl(&self.ident, capnp_path, &self.generics, options)
     }
     fn is_union(&self) -> bool {
         self.fields
@@ -233,114 +802,544 @@ impl EnumInfo {
     }
 }
 
+impl CodedEnumInfo {
+    fn generate_coded_impl(&self) -> TokenStream2 {
+        let rust_name = &self.ident;
+        let variants = &self.variants;
+        match &self.coding {
+            EnumCoding::Text {
+                values,
+                case_insensitive,
+            } => {
+                let error_message = format!("capnp_conv: unknown `{rust_name}` value {{:?}}");
+                let patterns = values.iter().map(|value| {
+                    if *case_insensitive {
+                        quote!(text if text.eq_ignore_ascii_case(#value))
+                    } else {
+                        quote!(#value)
+                    }
+                });
+                quote! {
+                  impl ::capnp_conv::TextEnum for #rust_name {
+                    fn from_text(text: &str) -> ::capnp::Result<Self> {
+                      match text {
+                        #(#patterns => Ok(Self::#variants),)*
+                        _ => Err(::capnp::Error::failed(::std::format!(#error_message, text))),
+                      }
+                    }
+                    fn to_text(&self) -> &'static str {
+                      match self {
+                        #(Self::#variants => #values,)*
+                      }
+                    }
+                  }
+                }
+            }
+            EnumCoding::Int { values } => {
+                let error_message = format!("capnp_conv: unknown `{rust_name}` code {{}}");
+                let values: Vec<Literal> =
+                    values.iter().map(|v| Literal::i64_unsuffixed(*v)).collect();
+                quote! {
+                  impl ::capnp_conv::IntEnum for #rust_name {
+                    fn from_int(code: i64) -> ::capnp::Result<Self> {
+                      match code {
+                        #(#values => Ok(Self::#variants),)*
+                        _ => Err(::capnp::Error::failed(::std::format!(#error_message, code))),
+                      }
+                    }
+                    fn to_int(&self) -> i64 {
+                      match self {
+                        #(Self::#variants => #values,)*
+                      }
+                    }
+                  }
+                }
+            }
+        }
+    }
+}
+
 impl FieldInfo {
-    fn generate_field_reader(&self, pre_fetched: bool) -> TokenStream2 {
+    /// Reads the field, `generics` being those of the item, which is needed in `read_lenient`
+    fn generate_field_reader(
+        &self,
+        pre_fetched: bool,
+        mode: ReadMode,
+        generics: &[Ident],
+    ) -> TokenStream2 {
         if matches!(self.field_type, FieldType::Phantom) {
             quote!(::std::marker::PhantomData)
         } else if self.skip_read {
-            let field_reader = match &self.default_override {
-                Some(default_override) => quote!(#default_override()),
-                None => self.generate_default_reader(),
-            };
+            let field_reader = self.generate_default_value();
             if self.is_optional {
                 quote!(Some(#field_reader))
             } else {
                 field_reader
             }
         } else {
-            let capnp_field_name = self.get_capnp_name(ToSnakeCase::to_snake_case);
-            if self.is_optional {
-                let field_reader =
-                    self.field_type
-                        .generate_field_reader(quote!(reader), &capnp_field_name, false);
-                if is_ptr_type(&self.field_type) {
-                    let checker = format_ident!("has_{}", capnp_field_name);
-                    quote! {
-                      match reader.#checker() {
-                        true => Some(#field_reader),
-                        false => None,
-                      }
-                    }
-                } else {
-                    quote!(Some(#field_reader))
-                }
+            let context = format!(
+                "field `{}` (capnp `{}`)",
+                self.rust_name,
+                self.get_capnp_name(ToLowerCamelCase::to_lower_camel_case)
+            );
+            let lenient_default = self
+                .generate_lenient_default(generics)
+                .filter(|_| mode.lenient);
+            let has_lenient_default = lenient_default.is_some();
+            let position = self.generate_read_position(lenient_default, mode);
+            let field_reader = generate_read_hooks(
+                self.generate_stored_field_reader(pre_fetched, mode, &position),
+                &self.hooks,
+                &context,
+                mode.typed_errors,
+                &position,
+            );
+            if has_lenient_default {
+                quote!({ 'field: { #field_reader } })
             } else {
-                let reader_name = if pre_fetched {
-                    quote!(val)
-                } else {
-                    quote!(reader)
-                };
-                self.field_type
-                    .generate_field_reader(reader_name, &capnp_field_name, pre_fetched)
+                field_reader
             }
         }
     }
 
-    fn generate_default_reader(&self) -> TokenStream2 {
-        let path = match &self.field_type {
-            FieldType::Void() => return quote!(()),
-            FieldType::Primitive(path) => path,
-            FieldType::Data(path) => path,
-            FieldType::Text(path) => path,
-            FieldType::Struct(path) => path,
-            FieldType::EnumRemote(path) => path,
-            FieldType::Enum(path) => path,
-            FieldType::GroupOrUnion(path) => path,
-            FieldType::UnnamedUnion(path) => path,
-            FieldType::List(_) => return quote!(Vec::default()),
-            FieldType::GenericStruct(path) => path,
-            _ => unimplemented!(),
-        };
-        let path = as_turbofish(path);
-        quote!(#path::default())
+    /// The value that replaces the field when it fails to read in `read_lenient`
+    ///
+    /// `None` when its `Default` may depend on the `generics` of the item, which have no `Default`
+    /// bound, the read fails instead
+    fn generate_lenient_default(&self, generics: &[Ident]) -> Option<TokenStream2> {
+        if self.is_optional {
+            Some(quote!(None))
+        } else if self.default_override.is_none()
+            && !matches!(self.field_type, FieldType::List(_))
+            && mentions_any(&self.rust_type, generics)
+        {
+            None
+        } else {
+            Some(self.generate_default_value())
+        }
     }
 
-    fn generate_field_writer_from_reference(&self) -> TokenStream2 {
-        if self.skip_write || matches!(self.field_type, FieldType::Phantom) {
+    /// The position of a read of the field
+    fn generate_read_position(
+        &self,
+        lenient_default: Option<TokenStream2>,
+        mode: ReadMode,
+    ) -> ReadPosition {
+        ReadPosition::field(
+            &self.rust_name.to_string(),
+            &self.get_capnp_name(ToLowerCamelCase::to_lower_camel_case),
+            lenient_default,
+            mode.typed_errors,
+        )
+    }
+
+    /// The return type and reader of the method of the field in `{Item}View`, reading it from
+    /// `reader`
+    ///
+    /// Struct fields that are read as they are give the view of the struct, union fields give
+    /// `None` unless their variant is set
+    fn generate_view_reader(
+        &self,
+        capnp_path: &Path,
+        has_other: bool,
+        mode: ReadMode,
+    ) -> (TokenStream2, TokenStream2) {
+        let rust_type = self.rust_type.to_token_stream();
+        if self.is_union_field {
+            let variant_name = self.get_capnp_ident(ToUpperCamelCase::to_upper_camel_case);
+            let pattern =
+                wrap_which_pattern(quote!(#capnp_path::Which::#variant_name(val)), has_other);
+            let which = if has_other {
+                quote!(reader.which())
+            } else {
+                quote!(reader.which()?)
+            };
+            let field_reader = self.generate_field_reader(true, mode, &[]);
+            let union_reader = quote! {
+              #[allow(unreachable_patterns)]
+              match #which {
+                #pattern => Some(#field_reader),
+                _ => None,
+              }
+            };
+            return (rust_type, union_reader);
+        }
+        match &self.field_type {
+            FieldType::Struct(path)
+                if !self.is_optional
+                    && !self.is_required
+                    && self.default_override.is_none()
+                    && self.hooks.after_read.is_none()
+                    && self.hooks.validate.is_none() =>
+            {
+                let getter = format_ident!(
+                    "get_{}",
+                    self.get_capnp_name(ToSnakeCase::to_snake_case),
+                    span = self.get_capnp_name_span()
+                );
+                let view_type = quote_spanned! {type_span(path)=>
+                  <#path as ::capnp_conv::Viewable>::View<'a>
+                };
+                let reader = self
+                    .generate_read_position(None, mode)
+                    .generate_try(quote!(reader.#getter()));
+                let view_reader = quote_spanned! {type_span(path)=>
+                  <#path as ::capnp_conv::Viewable>::view(#reader)
+                };
+                (view_type, view_reader)
+            }
+            _ => (rust_type, self.generate_field_reader(false, mode, &[])),
+        }
+    }
+
+    /// Reads the field into `self`, in place when it is not optional, a union field, defaulted,
+    /// required, or transformed by hooks
+    fn generate_field_read_into(&self, mode: ReadMode) -> TokenStream2 {
+        let rust_name = &self.rust_name;
+        let in_place = !self.skip_read
+            && !self.is_optional
+            && !self.is_union_field
+            && !self.is_required
+            && self.default_override.is_none()
+            && self.parallel.is_none()
+            && self.hooks.after_read.is_none()
+            && self.hooks.validate.is_none();
+        let field_reader = in_place
+            .then(|| {
+                self.field_type.generate_read_into(
+                    quote!(self.#rust_name),
+                    &self.get_capnp_name(ToSnakeCase::to_snake_case),
+                    self.get_capnp_name_span(),
+                    mode,
+                    &self.generate_read_position(None, mode),
+                )
+            })
+            .flatten();
+        match field_reader {
+            Some(field_reader) => field_reader,
+            None if matches!(self.field_type, FieldType::Phantom) => quote!(),
+            None => {
+                let field_reader = self.generate_field_reader(false, mode, &[]);
+                quote!(self.#rust_name = #field_reader;)
+            }
+        }
+    }
+
+    /// The `default` of the field, or its `Default`
+    fn generate_default_value(&self) -> TokenStream2 {
+        match &self.default_override {
+            Some(default_override) => self.generate_default_override(default_override),
+            None => self.generate_default_reader(),
+        }
+    }
+
+    /// Reads the field from the capnp struct, handling unset pointer fields
+    fn generate_stored_field_reader(
+        &self,
+        pre_fetched: bool,
+        mode: ReadMode,
+        position: &ReadPosition,
+    ) -> TokenStream2 {
+        let capnp_field_name = self.get_capnp_name(ToSnakeCase::to_snake_case);
+        let name_span = self.get_capnp_name_span();
+        let checker = format_ident!("has_{}", capnp_field_name, span = name_span);
+        if self.is_optional {
+            let field_reader = self.generate_type_reader(
+                quote!(reader),
+                &capnp_field_name,
+                name_span,
+                false,
+                mode,
+                position,
+            );
+            if is_ptr_type(&self.field_type) {
+                quote! {
+                  match reader.#checker() {
+                    true => Some(#field_reader),
+                    false => None,
+                  }
+                }
+            } else {
+                quote!(Some(#field_reader))
+            }
+        } else if self.is_required {
+            let field_reader = self.generate_type_reader(
+                quote!(reader),
+                &capnp_field_name,
+                name_span,
+                false,
+                mode,
+                position,
+            );
+            let message = format!(
+                "capnp_conv: required field `{}` (capnp `{}`) is not set",
+                self.rust_name,
+                capnp_field_name.to_lower_camel_case()
+            );
+            let read_error = generate_read_error_type();
+            let fail = position.generate_fail(quote! {
+              <#read_error as ::core::convert::From<::capnp::Error>>::from(
+                ::capnp::Error::failed(::std::string::String::from(#message))
+              )
+            });
+            quote! {
+              match reader.#checker() {
+                true => #field_reader,
+                false => #fail,
+              }
+            }
+        } else if let Some(default_override) = &self.default_override {
+            let field_reader = self.generate_type_reader(
+                quote!(reader),
+                &capnp_field_name,
+                name_span,
+                false,
+                mode,
+                position,
+            );
+            let default_override = self.generate_default_override(default_override);
+            quote! {
+              match reader.#checker() {
+                true => #field_reader,
+                false => #default_override,
+              }
+            }
+        } else {
+            let reader_name = if pre_fetched {
+                quote!(val)
+            } else {
+                quote!(reader)
+            };
+            self.generate_type_reader(
+                reader_name,
+                &capnp_field_name,
+                name_span,
+                pre_fetched,
+                mode,
+                position,
+            )
+        }
+    }
+
+    /// Reads the field's type from `reader_name`, reading `parallel` lists in parallel
+    ///
+    /// Reads with limits or for `read_lenient` are not parallel, they share their state
+    fn generate_type_reader(
+        &self,
+        reader_name: TokenStream2,
+        capnp_field_name: &str,
+        name_span: Span,
+        pre_fetched: bool,
+        mode: ReadMode,
+        position: &ReadPosition,
+    ) -> TokenStream2 {
+        match (&self.parallel, &self.field_type) {
+            (Some(min_len), FieldType::List(item_type)) if !mode.lenient && !mode.limited => {
+                let FieldType::Struct(path) = &**item_type else {
+                    unreachable!("`parallel` is only parsed on lists of structs")
+                };
+                let getter = format_ident!("get_{}", capnp_field_name, span = name_span);
+                let getter = if pre_fetched {
+                    reader_name
+                } else {
+                    respan(quote!(#reader_name.#getter()), name_span)
+                };
+                let list_reader = position.generate_try(getter);
+                position.generate_try_nested(quote_spanned! {type_span(path)=>
+                  ::capnp_conv::read_list_parallel::<#path>(#list_reader, #min_len)
+                })
+            }
+            _ => self.field_type.generate_field_reader(
+                reader_name,
+                capnp_field_name,
+                name_span,
+                pre_fetched,
+                mode,
+                position,
+            ),
+        }
+    }
+
+    fn generate_default_override(&self, default_override: &Expr) -> TokenStream2 {
+        if matches!(self.field_type, FieldType::Text(_)) {
+            quote!(::std::string::String::from(#default_override))
+        } else {
+            quote!(#default_override)
+        }
+    }
+
+    fn generate_default_reader(&self) -> TokenStream2 {
+        let path = match &self.field_type {
+            FieldType::Void() => return quote!(()),
+            FieldType::Primitive(path) => path,
+            FieldType::CheckedPrimitive(path, _) => path,
+            FieldType::Int128(path, _) => path,
+            FieldType::Data(path) => path,
+            FieldType::Text(path) => path,
+            FieldType::Struct(path) => path,
+            FieldType::EnumRemote(path) => path,
+            FieldType::Enum(path) => path,
+            FieldType::TextEnum(path) => path,
+            FieldType::IntEnum(path) => path,
+            FieldType::Flags(path, _) => path,
+            FieldType::GroupOrUnion(path) => path,
+            FieldType::UnnamedUnion(path) => path,
+            FieldType::List(_) => return quote!(Vec::default()),
+            FieldType::GenericStruct(path) => path,
+            FieldType::Phantom | FieldType::EnumVariant => {
+                unreachable!("phantom fields and enum variants are never defaulted")
+            }
+        };
+        let path = as_turbofish(path);
+        quote!(#path::default())
+    }
+
+    fn generate_field_writer_from_reference(&self, mode: WriteMode) -> TokenStream2 {
+        if self.skip_write || matches!(self.field_type, FieldType::Phantom) {
             quote! {} //noop
         } else {
             let capnp_field_name = self.get_capnp_name(ToSnakeCase::to_snake_case);
+            let name_span = self.get_capnp_name_span();
             let field_name = quote!(val);
             if self.is_optional {
-                let field_writer =
-                    self.field_type
-                        .generate_field_writer(&field_name, &capnp_field_name, false);
+                let field_writer = self.field_type.generate_field_writer(
+                    &field_name,
+                    &capnp_field_name,
+                    name_span,
+                    false,
+                    mode,
+                );
                 quote! {
                   if let Some(val) = val {
                     #field_writer;
                   }
                 }
             } else {
-                self.field_type
-                    .generate_field_writer(&field_name, &capnp_field_name, false)
+                self.field_type.generate_field_writer(
+                    &field_name,
+                    &capnp_field_name,
+                    name_span,
+                    false,
+                    mode,
+                )
             }
         }
     }
-    fn generate_field_writer_from_owned(&self) -> TokenStream2 {
+    fn generate_field_writer_from_owned(&self, mode: WriteMode) -> TokenStream2 {
         let rust_field_name = &self.rust_name;
         let capnp_field_name = self.get_capnp_name(ToSnakeCase::to_snake_case);
+        let name_span = self.get_capnp_name_span();
 
         if self.skip_write || matches!(self.field_type, FieldType::Phantom) {
-            quote! {} //noop
-        } else if self.is_optional {
+            return quote! {}; //noop
+        }
+
+        let (value, before_write) = match &self.hooks.before_write {
+            Some(before_write) => (
+                quote!(value),
+                Some(quote!(let value = #before_write(&this.#rust_field_name);)),
+            ),
+            None => (quote!(this.#rust_field_name), None),
+        };
+
+        let field_writer = if self.is_optional {
             let field_name = quote!(val);
-            let field_writer =
-                self.field_type
-                    .generate_field_writer(field_name, &capnp_field_name, false);
+            let field_writer = self.field_type.generate_field_writer(
+                field_name,
+                &capnp_field_name,
+                name_span,
+                false,
+                mode,
+            );
             quote! {
-              if let Some(val) = &self.#rust_field_name {
+              if let Some(val) = &#value {
                 #field_writer;
               }
             }
         } else {
-            self.field_type.generate_field_writer(
-                quote!(self.#rust_field_name),
-                &capnp_field_name,
-                true,
-            )
+            self.field_type
+                .generate_field_writer(&value, &capnp_field_name, name_span, true, mode)
+        };
+
+        match before_write {
+            Some(before_write) => quote!({ #before_write #field_writer }),
+            None => field_writer,
         }
     }
 
+    /// The words the field takes outside of the struct section, from a reference to its value
+    ///
+    /// `None` if it takes none
+    fn generate_size_words(&self, value: impl ToTokens) -> Option<TokenStream2> {
+        if self.skip_write {
+            return None;
+        }
+        let size = self.field_type.generate_size_words(&quote!(val))?;
+        let value = if let Some(before_write) = &self.hooks.before_write {
+            quote!(&#before_write(#value))
+        } else {
+            quote!(#value)
+        };
+        Some(if self.is_optional || self.is_union_field {
+            quote! {
+              match #value {
+                Some(val) => #size,
+                None => 0,
+              }
+            }
+        } else {
+            quote!({
+              let val = #value;
+              #size
+            })
+        })
+    }
+
+    /// Traits the capnp type does not implement, whose errors name the capnp field, and the
+    /// accessors of the field that require them
+    fn generate_accessor_fallbacks(&self, capnp_type: &str) -> (TokenStream2, TokenStream2) {
+        let accessor_name = self.get_capnp_name(ToSnakeCase::to_snake_case);
+        let capnp_name = self.get_capnp_name(ToLowerCamelCase::to_lower_camel_case);
+        let rust_name = &self.rust_name;
+        let note = if self.capnp_name_override.is_some() {
+            format!(
+                "`{rust_name}` maps to the capnp field `{capnp_name}` of its `name`, which needs a \
+                type `{rust_name}` converts to"
+            )
+        } else {
+            format!(
+                "`{rust_name}` maps to the capnp field `{capnp_name}`, its name in camelCase, which \
+                needs a type `{rust_name}` converts to, `#[capnp_conv(name = ...)]` maps it to \
+                another field"
+            )
+        };
+        let init_args = matches!(self.field_type, FieldType::List(_)).then(|| quote!(_: u32));
+        let (markers, accessors): (Vec<_>, Vec<_>) = [
+            ("get", quote!()),
+            ("set", quote!(_: V)),
+            ("init", quote!(#init_args)),
+            ("has", quote!()),
+        ]
+        .into_iter()
+        .map(|(prefix, args)| {
+            let accessor = format_ident!("{prefix}_{accessor_name}");
+            let marker = format_ident!("HasCapnpAccessor_{accessor}");
+            let message =
+                format!("`{capnp_type}` has no `{accessor}` for the field `{capnp_name}`");
+            let label = format!("no `{accessor}` in `{capnp_type}`");
+            let marker_trait = quote! {
+              #[diagnostic::on_unimplemented(message = #message, label = #label, note = #note)]
+              trait #marker {}
+            };
+            let accessor = quote! {
+              fn #accessor<V>(&mut self, #args) -> ! where Self: #marker { unreachable!() }
+            };
+            (marker_trait, accessor)
+        })
+        .unzip();
+        (quote!(#(#markers)*), quote!(#(#accessors)*))
+    }
+
     fn get_capnp_name(&self, to_case: impl FnOnce(&str) -> String) -> String {
         to_case(
             self.capnp_name_override
@@ -350,6 +1349,24 @@ impl FieldInfo {
                 .as_str(),
         )
     }
+
+    /// The capnp name as an identifier, located at the name it is derived from
+    fn get_capnp_ident(&self, to_case: impl FnOnce(&str) -> String) -> Ident {
+        format_ident!(
+            "{}",
+            self.get_capnp_name(to_case),
+            span = self.get_capnp_name_span()
+        )
+    }
+
+    /// The location of the name the capnp accessors are derived from, for errors about missing
+    /// accessors
+    ///
+    /// Keeps the hygiene of the call site so the generated code can use its own locals
+    fn get_capnp_name_span(&self) -> Span {
+        let name = self.capnp_name_override.as_ref().unwrap_or(&self.rust_name);
+        Span::call_site().located_at(name.span())
+    }
 }
 
 impl FieldType {
@@ -357,97 +1374,347 @@ impl FieldType {
         &self,
         reader_name: impl ToTokens,
         capnp_field_name: &str,
+        name_span: Span,
         reader_pre_fetched: bool,
+        mode: ReadMode,
+        position: &ReadPosition,
     ) -> TokenStream2 {
-        let getter = format_ident!("get_{}", capnp_field_name);
+        let getter = format_ident!("get_{}", capnp_field_name, span = name_span);
         let getter = if reader_pre_fetched {
             quote!(#reader_name)
         } else {
-            quote!(#reader_name.#getter())
+            respan(quote!(#reader_name.#getter()), name_span)
         };
         match self {
             FieldType::Phantom => unimplemented!(),
             FieldType::EnumVariant => unimplemented!(),
             FieldType::Void() => quote!(()),
             FieldType::Primitive(_) => quote!(#getter),
-            FieldType::Data(_) => quote!(#getter?.to_owned()),
-            FieldType::Text(_) => quote!(#getter?.to_string()?),
-            FieldType::Struct(struct_path) => {
-                let struct_path = as_turbofish(struct_path);
-                quote!(#struct_path::read(#getter?)?)
-            }
-            FieldType::EnumRemote(_) => quote!(#getter?.into()),
-            FieldType::Enum(_) => quote!(#getter?),
-            FieldType::GroupOrUnion(path) => {
-                let path = as_turbofish(path);
-                quote!(#path::read(#getter)?)
-            }
-            FieldType::UnnamedUnion(union_path) => {
-                let union_path = as_turbofish(union_path);
-                quote!(#union_path::read(#reader_name)?)
+            FieldType::CheckedPrimitive(path, capnp_type) => {
+                let schema_field_name = capnp_field_name.to_lower_camel_case();
+                position.generate_try(quote!(::capnp_conv::read_checked::<#capnp_type, #path>(#getter, #schema_field_name)))
             }
-            FieldType::List(item_type) => {
-                let item_getter = item_type.generate_struct_field_reader_list_item();
+            FieldType::Int128(path, Int128Encoding::Words) => {
+                let words = position.generate_try(getter);
                 quote! {
                   {
-                    let reader = #getter?;
-                    let size = reader.len();
-                    let mut list = Vec::with_capacity(size as usize);
-                    for idx in 0..size {
-                      list.push(#item_getter);
-                    }
-                    list
+                    let words = #words;
+                    <#path as ::capnp_conv::Int128>::from_words(words.get_hi(), words.get_lo())
                   }
                 }
             }
-            FieldType::GenericStruct(struct_path) => {
-                let struct_path = as_turbofish(struct_path);
-                quote!(#struct_path::read(#getter?)?)
+            FieldType::Int128(path, Int128Encoding::Data) => {
+                let data = position.generate_try(getter);
+                position.generate_try(quote!(<#path as ::capnp_conv::Int128>::from_data(#data)))
             }
+            FieldType::Data(_) => generate_data_read(
+                position.generate_try(getter),
+                capnp_field_name,
+                mode,
+                position,
+            ),
+            FieldType::Text(_) => generate_text_read(
+                position.generate_try(getter),
+                capnp_field_name,
+                mode,
+                position,
+            ),
+            FieldType::Struct(struct_path) | FieldType::GenericStruct(struct_path) => {
+                let reader = respan(position.generate_try(getter), type_span(struct_path));
+                generate_nested_read(struct_path, reader, mode, position)
+            }
+            FieldType::EnumRemote(path) => position.generate_try(
+                quote!(<#path as ::core::convert::TryFrom<u16>>::try_from(::capnp_conv::enum_ordinal(#getter))),
+            ),
+            FieldType::Enum(_) => position.generate_try(getter),
+            FieldType::TextEnum(path) => {
+                let text = position.generate_try(getter);
+                let text = position.generate_try(quote!(#text.to_str()));
+                position.generate_try(quote!(<#path as ::capnp_conv::TextEnum>::from_text(#text)))
+            }
+            FieldType::IntEnum(path) => {
+                position.generate_try(quote!(::capnp_conv::read_int_enum::<#path, _>(#getter)))
+            }
+            FieldType::Flags(path, undefined_bits) => position.generate_try(generate_flags_call(
+                path,
+                &quote!(::capnp_conv::read_flags::<#path>(#getter, #undefined_bits)),
+            )),
+            FieldType::GroupOrUnion(path) => generate_nested_read(path, getter, mode, position),
+            FieldType::UnnamedUnion(union_path) => generate_nested_read(
+                union_path,
+                respan(reader_name, type_span(union_path)),
+                mode,
+                position,
+            ),
+            FieldType::List(item_type) => item_type.generate_list_read(
+                position.generate_try(getter),
+                capnp_field_name,
+                mode,
+                position,
+            ),
         }
     }
 
-    fn generate_struct_field_reader_list_item(&self) -> TokenStream2 {
-        match self {
-            FieldType::Void() => quote!(()),
-            FieldType::Primitive(_) => quote!(reader.get(idx)),
-            FieldType::Data(_) => quote!(reader.get(idx)?.to_owned()),
-            FieldType::Text(_) => quote!(reader.get(idx)?.to_string()?),
-            FieldType::Struct(struct_path) => {
-                let struct_path = as_turbofish(struct_path);
-                quote!(#struct_path::read(reader.get(idx))?)
-            }
-            FieldType::EnumRemote(_) => quote!(reader.get(idx)?.into()),
-            FieldType::Enum(_) => quote!(reader.get(idx)?),
+    /// Reads the list of items of this type from `reader`, the list being at `position`
+    ///
+    /// Lists of primitives are copied in bulk, their reads cannot fail so there is no path to add
+    fn generate_list_read(
+        &self,
+        reader: impl ToTokens,
+        capnp_field_name: &str,
+        mode: ReadMode,
+        position: &ReadPosition,
+    ) -> TokenStream2 {
+        let list = generate_list_allocation(capnp_field_name, mode, position);
+        if let FieldType::Primitive(_) = self {
+            return quote! {
+              {
+                let reader = #reader;
+                let size = reader.len();
+                let mut list = #list;
+                ::capnp_conv::read_primitive_list(reader, &mut list);
+                list
+              }
+            };
+        }
+        let item_position = position.list_item();
+        let idx = item_position.index();
+        let item_reader =
+            self.generate_struct_field_reader_list_item(capnp_field_name, mode, &item_position);
+        quote! {
+          {
+            let reader = #reader;
+            let size = reader.len();
+            let mut list = #list;
+            for #idx in 0..size {
+              list.push(#item_reader);
+            }
+            list
+          }
+        }
+    }
+
+    /// Reads the field `capnp_field_name` of `reader` into the value at `target` in place,
+    /// `None` for types that are read whole
+    fn generate_read_into(
+        &self,
+        target: impl ToTokens,
+        capnp_field_name: &str,
+        name_span: Span,
+        mode: ReadMode,
+        position: &ReadPosition,
+    ) -> Option<TokenStream2> {
+        let getter = format_ident!("get_{}", capnp_field_name, span = name_span);
+        let getter = respan(quote!(reader.#getter()), name_span);
+        let field_reader = match self {
+            FieldType::Text(_) => {
+                let text = position.generate_try(&getter);
+                let text = position.generate_try(quote!(#text.to_str()));
+                quote! {
+                  let text = #text;
+                  #target.clear();
+                  #target.push_str(text);
+                }
+            }
+            FieldType::Data(_) => {
+                let data = position.generate_try(&getter);
+                quote! {
+                  let data = #data;
+                  #target.clear();
+                  #target.extend_from_slice(data);
+                }
+            }
+            FieldType::Struct(path) | FieldType::GenericStruct(path) => {
+                let reader = respan(position.generate_try(&getter), type_span(path));
+                generate_nested_read_into(path, quote!(&mut #target), reader, position)
+            }
+            FieldType::GroupOrUnion(path) => {
+                generate_nested_read_into(path, quote!(&mut #target), getter, position)
+            }
+            FieldType::UnnamedUnion(path) => generate_nested_read_into(
+                path,
+                quote!(&mut #target),
+                respan(quote!(reader), type_span(path)),
+                position,
+            ),
             FieldType::List(item_type) => {
-                let item_getter = item_type.generate_struct_field_reader_list_item();
+                let items_reader =
+                    item_type.generate_list_items_read_into(capnp_field_name, mode, position);
+                let reader = position.generate_try(&getter);
                 quote! {
-                  {
-                    let reader = reader.get(idx)?;
-                    let size = reader.len();
-                    let mut list = Vec::with_capacity(size as usize);
-                    for idx in 0..size {
-                      list.push(#item_getter);
+                  let reader = #reader;
+                  let size = reader.len();
+                  let list = &mut #target;
+                  #items_reader
+                }
+            }
+            _ => return None,
+        };
+        Some(quote!({ #field_reader }))
+    }
+
+    /// Reads the items of the list `reader` of `size` items of this type into `list`, reusing
+    /// the items it has, the list being at `position`
+    fn generate_list_items_read_into(
+        &self,
+        capnp_field_name: &str,
+        mode: ReadMode,
+        position: &ReadPosition,
+    ) -> TokenStream2 {
+        let item_position = position.list_item();
+        let idx = item_position.index();
+        let item_reader = match self {
+            FieldType::Primitive(_) => {
+                return quote! {
+                  list.clear();
+                  ::capnp_conv::read_primitive_list(reader, list);
+                };
+            }
+            FieldType::Struct(path) | FieldType::GenericStruct(path) => {
+                let item_reader = respan(quote!(reader.get(#idx)), type_span(path));
+                let read_into =
+                    generate_nested_read_into(path, quote!(item), &item_reader, &item_position);
+                let read = generate_nested_read(path, item_reader, mode, &item_position);
+                quote! {
+                  match list.get_mut(#idx as usize) {
+                    Some(item) => #read_into,
+                    None => list.push(#read),
+                  }
+                }
+            }
+            FieldType::Text(_) => {
+                let text = item_position.generate_try(quote!(reader.get(#idx)));
+                let text = item_position.generate_try(quote!(#text.to_str()));
+                quote! {
+                  let text = #text;
+                  match list.get_mut(#idx as usize) {
+                    Some(item) => {
+                      item.clear();
+                      item.push_str(text);
                     }
-                    list
+                    None => list.push(::std::string::String::from(text)),
                   }
                 }
             }
-            FieldType::GenericStruct(struct_path) => {
-                let struct_path = as_turbofish(struct_path);
-                quote!(#struct_path::read(reader.get(idx))?)
+            FieldType::Data(_) => {
+                let data = item_position.generate_try(quote!(reader.get(#idx)));
+                quote! {
+                  let data = #data;
+                  match list.get_mut(#idx as usize) {
+                    Some(item) => {
+                      item.clear();
+                      item.extend_from_slice(data);
+                    }
+                    None => list.push(data.to_vec()),
+                  }
+                }
+            }
+            _ => {
+                let item_reader = self.generate_struct_field_reader_list_item(
+                    capnp_field_name,
+                    mode,
+                    &item_position,
+                );
+                return quote! {
+                  list.clear();
+                  list.reserve(size as usize);
+                  for #idx in 0..size {
+                    list.push(#item_reader);
+                  }
+                };
+            }
+        };
+        quote! {
+          list.truncate(size as usize);
+          list.reserve(size as usize - list.len());
+          for #idx in 0..size {
+            #item_reader
+          }
+        }
+    }
+
+    /// Reads the item of this type at the index of `position` from the list `reader`
+    fn generate_struct_field_reader_list_item(
+        &self,
+        capnp_field_name: &str,
+        mode: ReadMode,
+        position: &ReadPosition,
+    ) -> TokenStream2 {
+        let schema_field_name = capnp_field_name.to_lower_camel_case();
+        let idx = position.index();
+        let item = quote!(reader.get(#idx));
+        match self {
+            FieldType::Void() => quote!(()),
+            FieldType::Primitive(_) => item,
+            FieldType::CheckedPrimitive(path, capnp_type) => position.generate_try(
+                quote!(::capnp_conv::read_checked::<#capnp_type, #path>(#item, #schema_field_name)),
+            ),
+            FieldType::Int128(path, Int128Encoding::Words) => quote! {
+              {
+                let words = #item;
+                <#path as ::capnp_conv::Int128>::from_words(words.get_hi(), words.get_lo())
+              }
+            },
+            FieldType::Int128(path, Int128Encoding::Data) => {
+                let data = position.generate_try(item);
+                position.generate_try(quote!(<#path as ::capnp_conv::Int128>::from_data(#data)))
+            }
+            FieldType::Data(_) => generate_data_read(
+                position.generate_try(item),
+                capnp_field_name,
+                mode,
+                position,
+            ),
+            FieldType::Text(_) => generate_text_read(
+                position.generate_try(item),
+                capnp_field_name,
+                mode,
+                position,
+            ),
+            FieldType::Struct(struct_path) | FieldType::GenericStruct(struct_path) => {
+                let reader = respan(item, type_span(struct_path));
+                generate_nested_read(struct_path, reader, mode, position)
+            }
+            FieldType::EnumRemote(path) => position.generate_try(
+                quote!(<#path as ::core::convert::TryFrom<u16>>::try_from(::capnp_conv::enum_ordinal(#item))),
+            ),
+            FieldType::Enum(_) => position.generate_try(item),
+            FieldType::TextEnum(path) => {
+                let text = position.generate_try(item);
+                let text = position.generate_try(quote!(#text.to_str()));
+                position.generate_try(quote!(<#path as ::capnp_conv::TextEnum>::from_text(#text)))
             }
-            _ => unimplemented!(),
+            FieldType::IntEnum(path) => {
+                position.generate_try(quote!(::capnp_conv::read_int_enum::<#path, _>(#item)))
+            }
+            FieldType::Flags(path, undefined_bits) => position.generate_try(generate_flags_call(
+                path,
+                &quote!(::capnp_conv::read_flags::<#path>(#item, #undefined_bits)),
+            )),
+            FieldType::List(item_type) => item_type.generate_list_read(
+                position.generate_try(item),
+                capnp_field_name,
+                mode,
+                position,
+            ),
+            FieldType::Phantom
+            | FieldType::EnumVariant
+            | FieldType::GroupOrUnion(_)
+            | FieldType::UnnamedUnion(_) => unreachable!("rejected as list items by the parser"),
         }
     }
+    #[allow(clippy::too_many_lines)]
     fn generate_field_writer(
         &self,
         field: impl ToTokens,
         capnp_field_name: &str,
+        name_span: Span,
         is_owned: bool,
+        mode: WriteMode,
     ) -> TokenStream2 {
-        let setter = format_ident!("set_{}", capnp_field_name);
-        let initializer = format_ident!("init_{}", capnp_field_name);
+        let setter = format_ident!("set_{}", capnp_field_name, span = name_span);
+        let initializer = format_ident!("init_{}", capnp_field_name, span = name_span);
+        let schema_field_name = capnp_field_name.to_lower_camel_case();
         let (deref_field, ref_field) = if is_owned {
             (quote!(#field), quote!(&#field))
         } else {
@@ -458,72 +1725,580 @@ impl FieldType {
             FieldType::EnumVariant => unimplemented!(),
             FieldType::Void() => quote!(builder.#setter(())),
             FieldType::Primitive(_) => quote!(builder.#setter(#deref_field)),
-            FieldType::Data(_) => quote!(builder.#setter(#ref_field)),
-            FieldType::Text(_) => quote!(builder.#setter(#field.as_str())),
-            FieldType::Struct(_) => quote!(#field.write(builder.reborrow().#initializer())),
-            FieldType::EnumRemote(_) => {
-                quote!(builder.#setter(::capnp_conv::RemoteEnum::to_capnp_enum(#ref_field)))
+            FieldType::CheckedPrimitive(_, capnp_type) => mode.generate_fallible_set(
+                quote!(::capnp_conv::try_write_checked::<_, #capnp_type>(#deref_field, #schema_field_name)),
+                |value| quote!(builder.#setter(#value)),
+            ),
+            FieldType::Int128(_, Int128Encoding::Words) => quote! {
+              {
+                let (hi, lo) = ::capnp_conv::Int128::to_words(#deref_field);
+                let mut builder = builder.reborrow().#initializer();
+                builder.set_hi(hi);
+                builder.set_lo(lo);
+              }
+            },
+            FieldType::Int128(_, Int128Encoding::Data) => {
+                quote!(builder.#setter(&::capnp_conv::Int128::to_data(#deref_field)))
             }
-            FieldType::Enum(_) => quote!(builder.#setter(#deref_field)),
-            FieldType::GroupOrUnion(_) => {
-                quote!(#field.write(builder.reborrow().#initializer()))
+            FieldType::Data(_) => {
+                let len_check = mode
+                    .checked
+                    .then(|| quote!(::capnp_conv::list_len(#field.len(), #schema_field_name)?;));
+                quote! {
+                  {
+                    #len_check
+                    builder.#setter(#ref_field)
+                  }
+                }
             }
-            FieldType::UnnamedUnion(_) => quote!(#field.write(builder.reborrow())),
-            FieldType::List(item_type) => {
-                let field_setter = item_type.generate_struct_field_writer_list_item();
+            // `Text` blobs hold a NUL terminator
+            FieldType::Text(_) => {
+                let len_check = mode
+                    .checked
+                    .then(|| quote!(::capnp_conv::list_len(#field.len() + 1, #schema_field_name)?;));
                 quote! {
                   {
-                    let list = #ref_field;
-                    let size = list.len();
-                    let mut builder = builder.reborrow().#initializer(size as u32);
-                    for (idx, item) in list.iter().enumerate().take(size) {
-                      #field_setter
-                    }
+                    #len_check
+                    builder.#setter(#field.as_str())
+                  }
+                }
+            }
+            FieldType::Struct(path) => generate_nested_write(
+                path,
+                ref_field,
+                quote!(builder.reborrow().#initializer()),
+                mode,
+            ),
+            FieldType::EnumRemote(_) => {
+                let set_ordinal = generate_fallible_write(
+                    quote!(::capnp_conv::set_enum_ordinal(builder.reborrow(), #schema_field_name, ordinal)),
+                    mode,
+                );
+                quote! {
+                  match ::core::convert::TryFrom::try_from(u16::from(#ref_field)) {
+                    Ok(value) => builder.#setter(value),
+                    Err(::capnp::NotInSchema(ordinal)) => #set_ordinal,
                   }
                 }
             }
-            FieldType::GenericStruct(_) => {
-                quote!(#field.write(builder.reborrow().#initializer()))
+            FieldType::Enum(_) => quote!(builder.#setter(#deref_field)),
+            FieldType::TextEnum(_) => {
+                quote!(builder.#setter(::capnp_conv::TextEnum::to_text(#ref_field)))
+            }
+            FieldType::IntEnum(_) => mode.generate_fallible_set(
+                quote!(::capnp_conv::try_int_enum_code(#ref_field)),
+                |value| quote!(builder.#setter(#value)),
+            ),
+            FieldType::Flags(path, _) => {
+                let bits = generate_flags_call(path, &quote!(::capnp_conv::flags_bits(#ref_field)));
+                quote!(builder.#setter(#bits))
+            }
+            FieldType::GroupOrUnion(path) => generate_nested_write(
+                path,
+                ref_field,
+                quote!(builder.reborrow().#initializer()),
+                mode,
+            ),
+            FieldType::UnnamedUnion(path) => {
+                generate_nested_write(path, ref_field, quote!(builder.reborrow()), mode)
+            }
+            FieldType::List(item_type) => item_type.generate_list_write(
+                ref_field,
+                quote!(builder.reborrow().#initializer(size)),
+                capnp_field_name,
+                mode,
+            ),
+            FieldType::GenericStruct(path) => generate_nested_write(
+                path,
+                ref_field,
+                quote!(builder.reborrow().#initializer()),
+                mode,
+            ),
+        }
+    }
+    /// Writes the list of items of this type `list` to the builder `initializer` returns, which
+    /// is given the list's `size`
+    ///
+    /// Lists of primitives are copied in bulk
+    fn generate_list_write(
+        &self,
+        list: impl ToTokens,
+        initializer: impl ToTokens,
+        capnp_field_name: &str,
+        mode: WriteMode,
+    ) -> TokenStream2 {
+        let schema_field_name = capnp_field_name.to_lower_camel_case();
+        let items_writer = if let FieldType::Primitive(_) = self {
+            quote!(::capnp_conv::write_primitive_list(#initializer, list);)
+        } else {
+            let field_setter = self.generate_struct_field_writer_list_item(capnp_field_name, mode);
+            quote! {
+              let mut builder = #initializer;
+              for (idx, item) in list.iter().enumerate() {
+                #field_setter
+              }
             }
+        };
+        let size = if mode.checked {
+            quote!(::capnp_conv::list_len(list.len(), #schema_field_name)?)
+        } else {
+            quote!(list.len() as u32)
+        };
+        quote! {
+          {
+            let list = #list;
+            let size = #size;
+            #items_writer
+          }
         }
     }
-    fn generate_struct_field_writer_list_item(&self) -> TokenStream2 {
+
+    fn generate_struct_field_writer_list_item(
+        &self,
+        capnp_field_name: &str,
+        mode: WriteMode,
+    ) -> TokenStream2 {
+        let schema_field_name = capnp_field_name.to_lower_camel_case();
         match self {
             FieldType::Void() => quote!(builder.set(idx as u32, ())),
             FieldType::Primitive(_) => quote!(builder.set(idx as u32, *item)),
-            FieldType::Data(_) => quote!(builder.set(idx as u32, item)),
-            FieldType::Text(_) => quote!(builder.set(idx as u32, item)),
-            FieldType::Struct(_) => quote!(item.write(builder.reborrow().get(idx as u32))),
-            FieldType::EnumRemote(_) => {
-                quote!(builder.set(idx as u32, ::capnp_conv::RemoteEnum::to_capnp_enum(item)))
+            FieldType::CheckedPrimitive(_, capnp_type) => mode.generate_fallible_set(
+                quote!(::capnp_conv::try_write_checked::<_, #capnp_type>(*item, #schema_field_name)),
+                |value| quote!(builder.set(idx as u32, #value)),
+            ),
+            FieldType::Int128(_, Int128Encoding::Words) => quote! {
+              let (hi, lo) = ::capnp_conv::Int128::to_words(*item);
+              let mut builder = builder.reborrow().get(idx as u32);
+              builder.set_hi(hi);
+              builder.set_lo(lo);
+            },
+            FieldType::Int128(_, Int128Encoding::Data) => {
+                quote!(builder.set(idx as u32, &::capnp_conv::Int128::to_data(*item)))
             }
-            FieldType::Enum(_) => quote!(builder.set(idx as u32, *item)),
-            FieldType::List(item_type) => {
-                let field_setter = item_type.generate_struct_field_writer_list_item();
+            FieldType::Data(_) => {
+                let len_check = mode
+                    .checked
+                    .then(|| quote!(::capnp_conv::list_len(item.len(), #schema_field_name)?;));
+                quote! {
+                  #len_check
+                  builder.set(idx as u32, item)
+                }
+            }
+            FieldType::Text(_) => {
+                let len_check = mode
+                    .checked
+                    .then(|| quote!(::capnp_conv::list_len(item.len() + 1, #schema_field_name)?;));
+                quote! {
+                  #len_check
+                  builder.set(idx as u32, item)
+                }
+            }
+            FieldType::Struct(path) => generate_nested_write(
+                path,
+                quote!(item),
+                quote!(builder.reborrow().get(idx as u32)),
+                mode,
+            ),
+            FieldType::EnumRemote(_) => {
+                let set_ordinal = generate_fallible_write(
+                    quote!(::capnp_conv::set_enum_list_ordinal(builder.reborrow(), idx as u32, ordinal)),
+                    mode,
+                );
                 quote! {
-                  let list = item;
-                  let size = list.len();
-                  let mut builder = builder.reborrow().init(idx as u32, size as u32);
-                  for (idx, item) in list.iter().enumerate().take(size) {
-                    #field_setter
+                  match ::core::convert::TryFrom::try_from(u16::from(item)) {
+                    Ok(value) => builder.set(idx as u32, value),
+                    Err(::capnp::NotInSchema(ordinal)) => #set_ordinal,
                   }
                 }
             }
-            FieldType::GenericStruct(_) => {
-                quote!(item.write(builder.reborrow().get(idx as u32)))
+            FieldType::Enum(_) => quote!(builder.set(idx as u32, *item)),
+            FieldType::TextEnum(_) => {
+                quote!(builder.set(idx as u32, ::capnp_conv::TextEnum::to_text(item)))
+            }
+            FieldType::IntEnum(_) => mode.generate_fallible_set(
+                quote!(::capnp_conv::try_int_enum_code(item)),
+                |value| quote!(builder.set(idx as u32, #value)),
+            ),
+            FieldType::Flags(path, _) => {
+                let bits = generate_flags_call(path, &quote!(::capnp_conv::flags_bits(item)));
+                quote!(builder.set(idx as u32, #bits))
+            }
+            FieldType::List(item_type) => item_type.generate_list_write(
+                quote!(item),
+                quote!(builder.reborrow().init(idx as u32, size)),
+                capnp_field_name,
+                mode,
+            ),
+            FieldType::GenericStruct(path) => generate_nested_write(
+                path,
+                quote!(item),
+                quote!(builder.reborrow().get(idx as u32)),
+                mode,
+            ),
+            FieldType::Phantom
+            | FieldType::EnumVariant
+            | FieldType::GroupOrUnion(_)
+            | FieldType::UnnamedUnion(_) => unreachable!("rejected as list items by the parser"),
+        }
+    }
+}
+
+impl FieldType {
+    /// The words a value of the type takes outside of the struct section it is in, `value` being
+    /// a reference to it
+    ///
+    /// `None` if it takes none
+    fn generate_size_words(&self, value: &TokenStream2) -> Option<TokenStream2> {
+        match self {
+            FieldType::Phantom
+            | FieldType::EnumVariant
+            | FieldType::Void()
+            | FieldType::Primitive(_)
+            | FieldType::CheckedPrimitive(..)
+            | FieldType::Enum(_)
+            | FieldType::EnumRemote(_)
+            | FieldType::IntEnum(_)
+            | FieldType::Flags(..) => None,
+            FieldType::Int128(..) => Some(quote!(2)),
+            FieldType::Data(_) => Some(quote!(::capnp_conv::data_words(#value.len()))),
+            FieldType::Text(_) => Some(quote!(::capnp_conv::text_words(#value.len()))),
+            FieldType::TextEnum(_) => Some(quote! {
+              ::capnp_conv::text_words(::capnp_conv::TextEnum::to_text(#value).len())
+            }),
+            FieldType::Struct(path) | FieldType::GenericStruct(path) => {
+                Some(quote!(<#path as ::capnp_conv::Writable>::encoded_size_words(#value)))
+            }
+            // Groups and unions are in the section of the struct holding them
+            FieldType::GroupOrUnion(path) | FieldType::UnnamedUnion(path) => Some(quote! {
+              <#path as ::capnp_conv::Writable>::encoded_size_words(#value).saturating_sub(section)
+            }),
+            FieldType::List(item_type) => Some(item_type.generate_list_size_words(value)),
+        }
+    }
+    /// The words of a list of this type, `list` being a reference to it
+    fn generate_list_size_words(&self, list: &TokenStream2) -> TokenStream2 {
+        if let Some(bits) = self.list_item_bits() {
+            return quote!(::capnp_conv::list_words(#list.len(), #bits));
+        }
+        let item_size = self
+            .generate_size_words(&quote!(item))
+            .unwrap_or_else(|| quote!(0));
+        let items_size = quote!(#list.iter().map(|item| #item_size).sum::<u64>());
+        match self {
+            // Struct lists have a tag word, and their items include their sections
+            FieldType::Struct(_)
+            | FieldType::GenericStruct(_)
+            | FieldType::Int128(_, Int128Encoding::Words) => quote!(1 + #items_size),
+            _ => quote!(#list.len() as u64 + #items_size),
+        }
+    }
+    /// The bits of each element of a list of this type, if it is a list of primitives
+    fn list_item_bits(&self) -> Option<TokenStream2> {
+        let bits = match self {
+            FieldType::Void() => 0u64,
+            FieldType::Primitive(path) => primitive_bits(&path.segments.last().unwrap().ident),
+            FieldType::CheckedPrimitive(_, capnp_type) => primitive_bits(capnp_type),
+            FieldType::Enum(_) | FieldType::EnumRemote(_) => 16,
+            // The width of the field is only known to the capnp generated code
+            FieldType::IntEnum(_) => 64,
+            FieldType::Flags(path, _) => {
+                return Some(generate_flags_call(
+                    path,
+                    &quote!(::capnp_conv::flags_bit_width::<#path>()),
+                ))
             }
-            _ => unimplemented!(),
+            _ => return None,
+        };
+        Some(quote!(#bits))
+    }
+}
+
+/// Wraps `call`, to a function of the `bitflags` feature of `capnp_conv`, in the shim that fails
+/// the build at the flags type `path` when the feature is not enabled
+fn generate_flags_call(path: &Path, call: &TokenStream2) -> TokenStream2 {
+    quote_spanned!(path.span()=> ::capnp_conv::bitflags_call!(#call))
+}
+
+/// The bits of a capnp primitive, named by its rust type
+fn primitive_bits(ident: &Ident) -> u64 {
+    match ident.to_string().as_str() {
+        "bool" => 1,
+        "i8" | "u8" => 8,
+        "i16" | "u16" => 16,
+        "i32" | "u32" | "f32" => 32,
+        _ => 64,
+    }
+}
+
+impl ToTokens for UndefinedBits {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        tokens.extend(match self {
+            UndefinedBits::Retain => quote!(::capnp_conv::UndefinedBits::Retain),
+            UndefinedBits::Truncate => quote!(::capnp_conv::UndefinedBits::Truncate),
+            UndefinedBits::Error => quote!(::capnp_conv::UndefinedBits::Error),
+        });
+    }
+}
+
+/// Wraps a `Which` match pattern in `Ok` when the unknown discriminant is matched rather than
+/// propagated
+fn wrap_which_pattern(pattern: TokenStream2, has_other: bool) -> TokenStream2 {
+    if has_other {
+        quote!(Ok(#pattern))
+    } else {
+        pattern
+    }
+}
+
+/// Writes a union discriminant that has no matching rust variant, as is, so discriminants the
+/// local schema does not know are written back unchanged
+fn generate_union_discriminant_writer(
+    discriminant: impl ToTokens,
+    mode: WriteMode,
+) -> TokenStream2 {
+    generate_fallible_write(
+        quote! {
+          ::capnp_conv::set_union_discriminant::<<Self as ::capnp_conv::Writable>::OwnedType>(
+            builder.reborrow(),
+            #discriminant,
+          )
+        },
+        mode,
+    )
+}
+
+/// Runs `write`, a write that returns a `capnp::Result<()>`, `write` panics with its error
+fn generate_fallible_write(write: impl ToTokens, mode: WriteMode) -> TokenStream2 {
+    if mode.checked {
+        quote!(#write?)
+    } else {
+        quote!(::capnp_conv::expect_written(#write))
+    }
+}
+
+/// The error type of generated read closures
+fn generate_read_error_type() -> TokenStream2 {
+    quote!(::capnp_conv::ReadError<<Self as ::capnp_conv::Readable>::Error>)
+}
+
+/// Reads a nested `Readable` at `position`, converting its errors to those of the enclosing item,
+/// errors about `path` not being `Readable` point to the field type
+///
+/// When `lenient`, the nested value is read with `read_lenient` and its field errors are pushed to
+/// `lenient_errors`
+fn generate_nested_read(
+    path: &Path,
+    reader: impl ToTokens,
+    mode: ReadMode,
+    position: &ReadPosition,
+) -> TokenStream2 {
+    let span = type_span(path);
+    if mode.lenient {
+        let read = position.generate_try_nested(quote_spanned! {span=>
+          <#path as ::capnp_conv::Readable>::read_lenient(#reader)
+        });
+        let error = position.generate_error(position.generate_nested_error(quote!(error)));
+        quote! {
+          {
+            let (value, errors) = #read;
+            lenient_errors.extend(errors.into_iter().map(|error| #error));
+            value
+          }
+        }
+    } else if mode.limited {
+        position.generate_try_nested(quote_spanned! {span=>
+          <#path as ::capnp_conv::Readable>::read_in_context(#reader, read_context)
+        })
+    } else {
+        position.generate_try_nested(quote_spanned! {span=>
+          <#path as ::capnp_conv::Readable>::read_with_path(#reader)
+        })
+    }
+}
+
+/// `Readable::read_into`, which runs `body` to read the fields of `type_name` into `self`
+fn generate_read_into_fn(body: &TokenStream2, type_name: &str) -> TokenStream2 {
+    let read_error = generate_read_error_type();
+    quote! {
+      fn read_into(
+        &mut self,
+        reader: <Self::OwnedType as ::capnp::traits::Owned>::Reader<'_>
+      ) -> ::core::result::Result<(), #read_error> {
+        let mut read = || -> ::core::result::Result<(), #read_error> {
+          #body
+          Ok(())
+        };
+        read().map_err(|error| error.in_type(#type_name))
+      }
+    }
+}
+
+/// Reads a nested `Readable` at `position` into `target` in place, converting its errors to those
+/// of the enclosing item
+fn generate_nested_read_into(
+    path: &Path,
+    target: impl ToTokens,
+    reader: impl ToTokens,
+    position: &ReadPosition,
+) -> TokenStream2 {
+    position.generate_try_nested(quote_spanned! {type_span(path)=>
+      <#path as ::capnp_conv::Readable>::read_into(#target, #reader)
+    })
+}
+
+/// Writes a nested `Writable`, errors about `path` not being `Writable` point to the field type
+fn generate_nested_write(
+    path: &Path,
+    value: impl ToTokens,
+    builder: impl ToTokens,
+    mode: WriteMode,
+) -> TokenStream2 {
+    let span = type_span(path);
+    let value = respan(value, span);
+    let write = if mode.checked {
+        quote_spanned!(span=> <#path as ::capnp_conv::Writable>::try_write(#value, field_builder)?)
+    } else {
+        quote_spanned!(span=> <#path as ::capnp_conv::Writable>::write(#value, field_builder))
+    };
+    quote_spanned! {span=>
+      {
+        let field_builder = #builder;
+        #write
+      }
+    }
+}
+
+/// The location of a field type, for errors about the traits it lacks
+///
+/// Keeps the hygiene of the call site so the generated code can use its own locals
+fn type_span(path: &Path) -> Span {
+    Span::call_site().located_at(path.span())
+}
+
+/// Runs `after_read` then `validate` on the value produced by `reader`, `context` names the
+/// value in validation errors, which are at `position`
+///
+/// `validate` errors are converted to the item's `error` type when `typed_errors` is set, and
+/// formatted into a `capnp::Error` otherwise
+fn generate_read_hooks(
+    reader: TokenStream2,
+    hooks: &Hooks,
+    context: &str,
+    typed_errors: bool,
+    position: &ReadPosition,
+) -> TokenStream2 {
+    if hooks.after_read.is_none() && hooks.validate.is_none() {
+        return reader;
+    }
+    let after_read = hooks
+        .after_read
+        .as_ref()
+        .map(|after_read| quote!(let value = #after_read(value);));
+    let validate = hooks.validate.as_ref().map(|validate| {
+        let error = if typed_errors {
+            quote!(::core::convert::From::from(error))
+        } else {
+            let message = format!("capnp_conv: {context} failed validation: {{}}");
+            quote!(::core::convert::From::from(::capnp::Error::failed(
+                ::std::format!(#message, error)
+            )))
+        };
+        let fail = position.generate_fail(quote!(::capnp_conv::ReadError::new(#error)));
+        quote! {
+          if let Err(error) = #validate(&value) {
+            #fail;
+          }
+        }
+    });
+    quote! {
+      {
+        let value = #reader;
+        #after_read
+        #validate
+        value
+      }
+    }
+}
+
+/// Copies the `Data` from `reader`, checking its size when `limited`
+fn generate_data_read(
+    reader: impl ToTokens,
+    capnp_field_name: &str,
+    mode: ReadMode,
+    position: &ReadPosition,
+) -> TokenStream2 {
+    if mode.limited {
+        let schema_field_name = capnp_field_name.to_lower_camel_case();
+        let check =
+            position.generate_try(quote!(read_context.data(data.len(), #schema_field_name)));
+        quote! {
+          {
+            let data = #reader;
+            #check;
+            data.to_owned()
+          }
+        }
+    } else {
+        quote!(#reader.to_owned())
+    }
+}
+
+/// Copies the `Text` from `reader`, checking its size when `limited`
+fn generate_text_read(
+    reader: impl ToTokens,
+    capnp_field_name: &str,
+    mode: ReadMode,
+    position: &ReadPosition,
+) -> TokenStream2 {
+    if mode.limited {
+        let schema_field_name = capnp_field_name.to_lower_camel_case();
+        let check =
+            position.generate_try(quote!(read_context.text(text.len(), #schema_field_name)));
+        let text = position.generate_try(quote!(text.to_string()));
+        quote! {
+          {
+            let text = #reader;
+            #check;
+            #text
+          }
         }
+    } else {
+        position.generate_try(quote!(#reader.to_string()))
     }
 }
 
+/// Allocates the `Vec` for a list of `size` items, checking its size when `limited`
+fn generate_list_allocation(
+    capnp_field_name: &str,
+    mode: ReadMode,
+    position: &ReadPosition,
+) -> TokenStream2 {
+    if mode.limited {
+        let schema_field_name = capnp_field_name.to_lower_camel_case();
+        position.generate_try(quote!(read_context.list(size, #schema_field_name)))
+    } else {
+        quote!(Vec::with_capacity(size as usize))
+    }
+}
+
+/// `generate_body` generates the body of each write method, in the mode of that method, which
+/// writes `this`, being `self` or the result of the `before_write` hook
 fn generate_writable_impl(
     rust_name: impl ToTokens,
     capnp_path: impl ToTokens,
     generics: &[Ident],
-    func_body: impl ToTokens,
+    hooks: &Hooks,
+    generate_body: impl Fn(WriteMode) -> TokenStream2,
+    size_body: impl ToTokens,
 ) -> TokenStream2 {
     let capnp_generics: Vec<Ident> = generics.iter().map(to_capnp_generic).collect();
+    let this = if let Some(before_write) = &hooks.before_write {
+        quote!(let this = &#before_write(self);)
+    } else {
+        quote!(let this = self;)
+    };
+    let write_body = generate_body(WriteMode { checked: false });
+    let try_write_body = generate_body(WriteMode { checked: true });
     quote! {
       impl<#(#generics, #capnp_generics),*> ::capnp_conv::Writable for #rust_name<#(#generics),*>
       where
@@ -533,32 +2308,155 @@ fn generate_writable_impl(
         type OwnedType = #capnp_path::Owned<#(#capnp_generics),*>;
 
         fn write(&self, mut builder: <Self::OwnedType as ::capnp::traits::Owned>::Builder<'_>) {
-          #func_body
+          #this
+          #write_body
+        }
+
+        fn try_write(
+          &self,
+          mut builder: <Self::OwnedType as ::capnp::traits::Owned>::Builder<'_>
+        ) -> ::capnp::Result<()> {
+          #this
+          #try_write_body
+          Ok(())
+        }
+
+        fn encoded_size_words(&self) -> u64 {
+          #this
+          let section = ::capnp_conv::struct_words::<
+            <Self::OwnedType as ::capnp::traits::Owned>::Builder<'_>
+          >();
+          #size_body
         }
       }
     }
 }
 
+/// Runs the item hooks on the value `func_body` reads
+fn generate_item_read_hooks(
+    func_body: TokenStream2,
+    options: &ItemOptions,
+    type_name: &str,
+) -> TokenStream2 {
+    let hooks = &options.hooks;
+    if hooks.after_read.is_none() && hooks.validate.is_none() {
+        return func_body;
+    }
+    let read_error = generate_read_error_type();
+    let context = format!("`{type_name}`");
+    let reader = quote! {
+      {
+        let result: ::core::result::Result<Self, #read_error> = { #func_body };
+        result?
+      }
+    };
+    let value = generate_read_hooks(
+        reader,
+        hooks,
+        &context,
+        options.error.is_some(),
+        &ReadPosition::default(),
+    );
+    quote!(Ok(#value))
+}
+
+/// `generate_body` generates the body of each read method, in the mode of that method, and
+/// `read_into` overrides the default `read_into` when it is not empty
 fn generate_readable_impl(
     rust_name: impl ToTokens,
     capnp_path: impl ToTokens,
     generics: &[Ident],
-    func_body: impl ToTokens,
+    options: &ItemOptions,
+    generate_body: impl Fn(ReadMode) -> TokenStream2,
+    read_into: impl ToTokens,
 ) -> TokenStream2 {
+    let typed_errors = options.error.is_some();
     let capnp_generics: Vec<Ident> = generics.iter().map(to_capnp_generic).collect();
+    let error_type = generate_error_type(options);
+    let read_error = generate_read_error_type();
+    let type_name = rust_name.to_token_stream().to_string();
+    let add_item_hooks = |func_body| generate_item_read_hooks(func_body, options, &type_name);
+    let func_body = add_item_hooks(generate_body(ReadMode {
+        typed_errors,
+        lenient: false,
+        limited: false,
+    }));
+    let limited_body = add_item_hooks(generate_body(ReadMode {
+        typed_errors,
+        lenient: false,
+        limited: true,
+    }));
+    let read_lenient = options.lenient.then(|| {
+        let lenient_body = add_item_hooks(generate_body(ReadMode {
+            typed_errors,
+            lenient: true,
+            limited: false,
+        }));
+        quote! {
+          fn read_lenient(
+            reader: <Self::OwnedType as ::capnp::traits::Owned>::Reader<'_>
+          ) -> ::core::result::Result<(Self, ::std::vec::Vec<#read_error>), #read_error> {
+            let mut lenient_errors: ::std::vec::Vec<#read_error> = ::std::vec::Vec::new();
+            let mut read = || -> ::core::result::Result<Self, #read_error> {
+              #lenient_body
+            };
+            let value = read().map_err(|error| error.in_type(#type_name))?;
+            let errors = lenient_errors
+              .into_iter()
+              .map(|error| error.in_type(#type_name))
+              .collect();
+            Ok((value, errors))
+          }
+        }
+    });
+    let into_error = if options.error.is_some() {
+        quote!(|error| ::capnp::Error::from(error.into_capnp()))
+    } else {
+        quote!(<::capnp::Error as ::core::convert::From<::capnp_conv::ReadError>>::from)
+    };
+    let error_bounds = generate_error_bounds(generics, options);
     quote! {
       impl<#(#generics, #capnp_generics),*> ::capnp_conv::Readable for #rust_name<#(#generics),*>
       where
         #(#generics: ::capnp_conv::Readable<OwnedType = #capnp_generics>,)*
+        #error_bounds
         #(#capnp_generics: ::capnp::traits::Owned,)*
       {
         type OwnedType = #capnp_path::Owned<#(#capnp_generics),*>;
+        type Error = #error_type;
 
         fn read(
           reader: <Self::OwnedType as ::capnp::traits::Owned>::Reader<'_>
         ) -> ::capnp::Result<Self> {
-          #func_body
+          <Self as ::capnp_conv::Readable>::read_with_path(reader).map_err(#into_error)
+        }
+
+        fn read_with_path(
+          reader: <Self::OwnedType as ::capnp::traits::Owned>::Reader<'_>
+        ) -> ::core::result::Result<Self, #read_error> {
+          let read = move || -> ::core::result::Result<Self, #read_error> {
+            #func_body
+          };
+          read().map_err(|error| error.in_type(#type_name))
+        }
+
+        fn read_in_context(
+          reader: <Self::OwnedType as ::capnp::traits::Owned>::Reader<'_>,
+          read_context: &mut ::capnp_conv::ReadContext,
+        ) -> ::core::result::Result<Self, #read_error> {
+          if let Err(error) = read_context.enter(#type_name) {
+            return Err(<#read_error as ::core::convert::From<_>>::from(error).in_type(#type_name));
+          }
+          let mut read = || -> ::core::result::Result<Self, #read_error> {
+            #limited_body
+          };
+          let result = read().map_err(|error| error.in_type(#type_name));
+          read_context.exit();
+          result
         }
+
+        #read_into
+        #read_lenient
       }
     }
 }
@@ -567,21 +2465,52 @@ fn generate_try_from_impl(
     rust_name: impl ToTokens,
     capnp_path: impl ToTokens,
     generics: &[Ident],
+    options: &ItemOptions,
 ) -> TokenStream2 {
     let capnp_generics: Vec<Ident> = generics.iter().map(to_capnp_generic).collect();
+    let error_type = generate_error_type(options);
+    let error_bounds = generate_error_bounds(generics, options);
+    // `read` gives a `capnp::Error`, which has room for the path, other errors are the `cause` of
+    // the `ReadError`
+    let read = if options.error.is_some() {
+        quote!(::capnp_conv::Readable::read_with_path(reader).map_err(|error| error.cause))
+    } else {
+        quote!(::capnp_conv::Readable::read(reader))
+    };
     quote! {
       impl<'a, #(#generics, #capnp_generics),*>
       ::std::convert::TryFrom<#capnp_path::Reader<'a, #(#capnp_generics),*>>
       for #rust_name<#(#generics),*>
       where
         #(#generics: ::capnp_conv::Readable<OwnedType = #capnp_generics>,)*
+        #error_bounds
         #(#capnp_generics: ::capnp::traits::Owned,)*
       {
-        type Error = ::capnp::Error;
+        type Error = #error_type;
 
-        fn try_from(reader: #capnp_path::Reader<'a, #(#capnp_generics),*>) -> ::capnp::Result<Self> {
-          ::capnp_conv::Readable::read(reader)
+        fn try_from(
+          reader: #capnp_path::Reader<'a, #(#capnp_generics),*>
+        ) -> ::core::result::Result<Self, #error_type> {
+          #read
         }
       }
     }
 }
+
+/// The bounds an `error` type of the item needs to convert the errors of its generic fields,
+/// `capnp::Error` takes any error
+fn generate_error_bounds(generics: &[Ident], options: &ItemOptions) -> Option<TokenStream2> {
+    let error_type = options.error.as_ref()?;
+    Some(quote! {
+      #(#error_type: ::core::convert::From<<#generics as ::capnp_conv::Readable>::Error>,)*
+    })
+}
+
+/// The `Readable::Error` of an item
+fn generate_error_type(options: &ItemOptions) -> TokenStream2 {
+    if let Some(error_type) = &options.error {
+        error_type.to_token_stream()
+    } else {
+        quote!(::capnp::Error)
+    }
+}
@@ -3,21 +3,26 @@ mod models;
 mod parsers;
 mod utils;
 
-use models::ItemInfo;
+use models::{ItemInfo, ItemTarget};
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput, Path};
-use utils::is_capnp_attr;
+use syn::{parse_macro_input, DeriveInput};
+use utils::{error, is_capnp_attr};
 
 #[proc_macro_attribute]
 pub fn capnp_conv(attr_stream: TokenStream, input_stream: TokenStream) -> TokenStream {
-    let capnp_struct = parse_macro_input!(attr_stream as Path);
+    let target = match ItemTarget::parse(attr_stream.into()) {
+        Ok(target) => target,
+        Err(error) => return error.to_compile_error().into(),
+    };
     let mut input = parse_macro_input!(input_stream as DeriveInput);
 
-    match ItemInfo::parse_input(&input) {
+    match ItemInfo::parse_input(&input, &target) {
         Ok(item_info) => {
-            let output = item_info.generate_impls(&capnp_struct);
-            remove_capnp_field_attrs(&mut input);
+            let output = item_info.generate_impls(&target);
+            if let Err(error) = remove_capnp_field_attrs(&mut input) {
+                return error.to_compile_error().into();
+            }
             quote! {
               #input
               #output
@@ -28,7 +33,7 @@ pub fn capnp_conv(attr_stream: TokenStream, input_stream: TokenStream) -> TokenS
     .into()
 }
 
-fn remove_capnp_field_attrs(input: &mut DeriveInput) {
+fn remove_capnp_field_attrs(input: &mut DeriveInput) -> syn::Result<()> {
     match &mut input.data {
         syn::Data::Struct(data) => {
             for field in &mut data.fields {
@@ -40,8 +45,11 @@ fn remove_capnp_field_attrs(input: &mut DeriveInput) {
                 drain_filter(&mut variant.attrs, is_capnp_attr);
             }
         }
-        syn::Data::Union(_) => unimplemented!(),
+        syn::Data::Union(data) => {
+            return error(data.union_token.span, "expected one of: `struct`, `enum`");
+        }
     }
+    Ok(())
 }
 
 //not using nightly so we need to do this manually
@@ -1,17 +1,66 @@
 use proc_macro2::Ident;
-use syn::Path;
+use syn::{Expr, Path, Type, Visibility};
+
+/// Arguments of the item level `#[capnp_conv(...)]` attribute
+#[derive(Debug)]
+pub enum ItemTarget {
+    /// `#[capnp_conv(capnp_path, options...)]`, the capnp struct/group/union/enum the item maps to
+    Capnp(Path, Box<ItemOptions>),
+    /// `#[capnp_conv(type = "text_enum")]`, an enum stored as capnp `Text`
+    TextEnum { case_insensitive: bool },
+    /// `#[capnp_conv(type = "int_enum")]`, an enum stored as a capnp integer
+    IntEnum,
+}
+
+/// Comma separated options following the capnp path
+#[derive(Debug, Default)]
+pub struct ItemOptions {
+    /// `deny_missing`, makes every pointer field `required` unless it is optional or defaulted
+    pub deny_missing: bool,
+    /// `lenient`, generates `Readable::read_lenient`, which substitutes defaults for fields that
+    /// fail to read
+    pub lenient: bool,
+    /// `validate`, `after_read`, and `before_write`, run on the whole item
+    pub hooks: Hooks,
+    /// `error = "Type"`, the `Readable::Error` of the item in place of `capnp::Error`
+    pub error: Option<Type>,
+    /// `view`, generates a `{Item}View` that reads the fields of a reader on demand
+    pub view: bool,
+}
+
+/// User functions run around the conversion of an item or field of type `T`
+#[derive(Debug, Default)]
+pub struct Hooks {
+    /// `validate = "path"`, a `fn(&T) -> Result<(), E>` with `E: Display`, run last when reading
+    pub validate: Option<Path>,
+    /// `after_read = "path"`, a `fn(T) -> T` run on the read value before `validate`
+    pub after_read: Option<Path>,
+    /// `before_write = "path"`, a `fn(&T) -> T` whose result is written in place of the value
+    pub before_write: Option<Path>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.validate.is_none() && self.after_read.is_none() && self.before_write.is_none()
+    }
+}
 
 #[derive(Debug)]
 pub enum ItemInfo {
     Struct(StructInfo),
     Enum(EnumInfo),
+    CodedEnum(CodedEnumInfo),
 }
 
 #[derive(Debug)]
 pub struct StructInfo {
     pub ident: Ident,
+    pub vis: Visibility,
     pub fields: Vec<FieldInfo>,
     pub generics: Vec<Ident>,
+    /// `#[capnp_conv(other)] other: Option<u16>`, holds union discriminants missing from the local
+    /// schema
+    pub other_field: Option<Ident>,
 }
 
 #[derive(Debug)]
@@ -19,11 +68,37 @@ pub struct EnumInfo {
     pub ident: Ident,
     pub fields: Vec<FieldInfo>,
     pub generics: Vec<Ident>,
+    /// `#[capnp_conv(unknown)] Unknown(u16)`, holds enumerants missing from the local schema
+    pub unknown_variant: Option<Ident>,
+    /// `#[capnp_conv(other)] Other(u16)`, holds union discriminants missing from the local schema
+    pub other_variant: Option<Ident>,
+}
+
+/// A simple enum with no capnp counterpart, stored as `Text` or an integer
+#[derive(Debug)]
+pub struct CodedEnumInfo {
+    pub ident: Ident,
+    pub variants: Vec<Ident>,
+    pub coding: EnumCoding,
+}
+
+/// The values of each `CodedEnumInfo` variant, in the same order
+#[derive(Debug)]
+pub enum EnumCoding {
+    Text {
+        values: Vec<String>,
+        case_insensitive: bool,
+    },
+    Int {
+        values: Vec<i64>,
+    },
 }
 
 #[derive(Debug)]
 pub struct FieldInfo {
     pub rust_name: Ident,
+    /// The type of the field, `()` for unit variants
+    pub rust_type: Type,
     pub field_type: FieldType,
     pub capnp_name_override: Option<Ident>,
     pub has_phantom_in_variant: bool,
@@ -32,7 +107,12 @@ pub struct FieldInfo {
     pub _is_boxed: bool,
     pub skip_read: bool,
     pub skip_write: bool,
-    pub default_override: Option<Path>,
+    pub default_override: Option<Expr>,
+    /// `#[capnp_conv(required)]`, fails the read when the pointer field is unset
+    pub is_required: bool,
+    /// `#[capnp_conv(parallel)]`, the fewest items the list of structs is read in parallel from
+    pub parallel: Option<Expr>,
+    pub hooks: Hooks,
 }
 
 #[derive(Debug)]
@@ -44,6 +124,11 @@ pub enum FieldType {
     Void(),
     /// bool, i8/16/32/64, u8/16/32/64, f32/64
     Primitive(Path),
+    /// usize/isize/char, or integers with `#[capnp_conv(capnp_type = "...")]`
+    /// Range checked conversion to the capnp primitive in the second slot
+    CheckedPrimitive(Path, Ident),
+    /// u128/i128, stored as a struct with `hi`/`lo` words or as `Data`
+    Int128(Path, Int128Encoding),
     /// Vec<u8>
     Data(Path),
     /// String
@@ -63,12 +148,38 @@ pub enum FieldType {
     /// Requires field attribute `#[capnp_conv(type = "unnamed_union")]`
     /// Reader/writer acts as a "passthrough", not needing to get/init anything
     UnnamedUnion(Path),
+    /// Requires field attribute `#[capnp_conv(type = "text_enum")]`
+    /// Indicates an enum defined with `#[capnp_conv(type = "text_enum")]`, stored as `Text`
+    TextEnum(Path),
+    /// Requires field attribute `#[capnp_conv(type = "int_enum")]`
+    /// Indicates an enum defined with `#[capnp_conv(type = "int_enum")]`, stored as an integer
+    IntEnum(Path),
+    /// Requires field attribute `#[capnp_conv(type = "bitflags")]`
+    /// Indicates a `bitflags` type stored as an integer, requires the `bitflags` feature
+    Flags(Path, UndefinedBits),
     /// Vec<T>
     List(Box<FieldType>),
     /// CapnpStruct(T1, T2, ...)
     GenericStruct(Path),
 }
 
+/// How a `FieldType::Int128` is stored
+#[derive(Debug, Clone, Copy)]
+pub enum Int128Encoding {
+    /// A struct with `hi :UInt64` and `lo :UInt64` fields
+    Words,
+    /// 16 bytes of big-endian `Data`, with `#[capnp_conv(type = "data")]`
+    Data,
+}
+
+/// `#[capnp_conv(undefined_bits = "...")]`, mirrors `capnp_conv::UndefinedBits`
+#[derive(Debug, Clone, Copy)]
+pub enum UndefinedBits {
+    Retain,
+    Truncate,
+    Error,
+}
+
 #[derive(Debug)]
 pub enum FieldWrapper {
     None,
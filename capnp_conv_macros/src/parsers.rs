@@ -1,34 +1,160 @@
 use std::{
     collections::{hash_map, HashMap},
-    mem::discriminant,
+    mem::{discriminant, replace},
 };
 
-use proc_macro2::{Ident, TokenStream};
+use heck::ToSnakeCase;
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
 use quote::ToTokens;
 use syn::{
-    spanned::Spanned, Attribute, Data, DataEnum, DataStruct, DeriveInput, Field, Fields,
-    GenericArgument, GenericParam, Generics, LitStr, Path, PathArguments, Result, Type, Variant,
+    meta::ParseNestedMeta,
+    parse::{ParseStream, Parser},
+    parse_quote,
+    spanned::Spanned,
+    Attribute, Data, DataEnum, DataStruct, DeriveInput, Expr, ExprLit, ExprUnary, Field, Fields,
+    GenericArgument, GenericParam, Generics, Lit, LitStr, Path, PathArguments, Result, Token, Type,
+    UnOp, Variant, Visibility,
 };
 
 use crate::{
-    models::{EnumInfo, FieldInfo, FieldType, FieldWrapper, ItemInfo, StructInfo},
-    utils::{as_turbofish, error, is_capnp_attr, try_peel_type},
+    models::{
+        CodedEnumInfo, EnumCoding, EnumInfo, FieldInfo, FieldType, FieldWrapper, Hooks,
+        Int128Encoding, ItemInfo, ItemOptions, ItemTarget, StructInfo, UndefinedBits,
+    },
+    utils::{as_turbofish, error, is_capnp_attr, is_ptr_type, try_peel_type},
 };
 
+impl ItemTarget {
+    pub fn parse(tokens: TokenStream) -> Result<Self> {
+        let is_type = matches!(
+            tokens.clone().into_iter().next(),
+            Some(TokenTree::Ident(ident)) if ident == "type"
+        );
+        if !is_type {
+            return ItemTarget::parse_capnp(tokens);
+        }
+
+        let mut target = None;
+        let mut case_insensitive = None;
+        syn::meta::parser(|meta| {
+            if meta.path.is_ident("type") && target.is_none() {
+                let lit_str = meta.value()?.parse::<LitStr>()?;
+                target = match lit_str.value().as_str() {
+                    "text_enum" => Some(ItemTarget::TextEnum {
+                        case_insensitive: false,
+                    }),
+                    "int_enum" => Some(ItemTarget::IntEnum),
+                    _ => return Err(meta.error("expected `text_enum` or `int_enum`")),
+                };
+                Ok(())
+            } else if meta.path.is_ident("case_insensitive") && case_insensitive.is_none() {
+                case_insensitive = Some(meta.path.clone());
+                Ok(())
+            } else if meta.path.is_ident("type") || meta.path.is_ident("case_insensitive") {
+                Err(meta.error("duplicate attribute"))
+            } else {
+                Err(meta.error("expected `type` or `case_insensitive`"))
+            }
+        })
+        .parse2(tokens)?;
+
+        match (target, case_insensitive) {
+            (Some(ItemTarget::TextEnum { .. }), case_insensitive) => Ok(ItemTarget::TextEnum {
+                case_insensitive: case_insensitive.is_some(),
+            }),
+            (_, Some(path)) => error(
+                path.span(),
+                "`case_insensitive` can only be used with `text_enum`s",
+            ),
+            (target, None) => Ok(target.unwrap()),
+        }
+    }
+}
+
+impl ItemTarget {
+    /// Parses `capnp_path` followed by optional comma separated `ItemOptions`
+    fn parse_capnp(tokens: TokenStream) -> Result<Self> {
+        let (path, options_tokens) = (|input: ParseStream| {
+            let path = input.parse::<Path>()?;
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+            Ok((path, input.parse::<TokenStream>()?))
+        })
+        .parse2(tokens)?;
+
+        let mut options = ItemOptions::default();
+        syn::meta::parser(|meta| {
+            let is_duplicate = if meta.path.is_ident("deny_missing") {
+                replace(&mut options.deny_missing, true)
+            } else if meta.path.is_ident("lenient") {
+                replace(&mut options.lenient, true)
+            } else if meta.path.is_ident("error") {
+                let error_type = meta.value()?.parse::<LitStr>()?.parse::<Type>()?;
+                options.error.replace(error_type).is_some()
+            } else if meta.path.is_ident("view") {
+                replace(&mut options.view, true)
+            } else if let Some(hook) = options.hooks.get_mut(&meta.path) {
+                hook.replace(parse_hook(&meta)?).is_some()
+            } else {
+                return Err(meta.error(
+                    "expected `deny_missing`, `lenient`, `error`, `view`, `validate`, \
+                    `after_read`, or `before_write`",
+                ));
+            };
+            if is_duplicate {
+                Err(meta.error("duplicate attribute"))
+            } else {
+                Ok(())
+            }
+        })
+        .parse2(options_tokens)?;
+
+        Ok(ItemTarget::Capnp(path, Box::new(options)))
+    }
+}
+
 impl ItemInfo {
-    pub fn parse_input(input: &DeriveInput) -> Result<Self> {
-        match &input.data {
-            Data::Struct(struct_data) => Ok(ItemInfo::Struct(StructInfo::parse_struct(
-                &input.ident,
-                &input.generics,
-                struct_data,
-            )?)),
-            Data::Enum(enum_data) => Ok(ItemInfo::Enum(EnumInfo::parse_enum(
-                &input.ident,
-                &input.generics,
-                enum_data,
-            )?)),
-            Data::Union(union_data) => error(
+    pub fn parse_input(input: &DeriveInput, target: &ItemTarget) -> Result<Self> {
+        match (&input.data, target) {
+            (Data::Struct(_), ItemTarget::Capnp(_, options))
+                if options.view && !input.generics.params.is_empty() =>
+            {
+                error(
+                    input.generics.span(),
+                    "`view` cannot be used on generic structs",
+                )
+            }
+            (Data::Struct(struct_data), ItemTarget::Capnp(_, options)) => {
+                Ok(ItemInfo::Struct(StructInfo::parse_struct(
+                    &input.ident,
+                    &input.vis,
+                    &input.generics,
+                    struct_data,
+                    options,
+                )?))
+            }
+            (Data::Enum(enum_data), ItemTarget::Capnp(_, options)) if options.deny_missing => {
+                error(
+                    enum_data.enum_token.span(),
+                    "`deny_missing` can only be used on structs",
+                )
+            }
+            (Data::Enum(enum_data), ItemTarget::Capnp(_, options)) if options.view => error(
+                enum_data.enum_token.span(),
+                "`view` can only be used on structs",
+            ),
+            (Data::Enum(enum_data), ItemTarget::Capnp(_, options)) => Ok(ItemInfo::Enum(
+                EnumInfo::parse_enum(&input.ident, &input.generics, enum_data, options)?,
+            )),
+            (Data::Enum(enum_data), target) => Ok(ItemInfo::CodedEnum(
+                CodedEnumInfo::parse_coded_enum(&input.ident, &input.generics, enum_data, target)?,
+            )),
+            (Data::Struct(struct_data), _) => error(
+                struct_data.struct_token.span(),
+                "`text_enum`s and `int_enum`s must be enums",
+            ),
+            (Data::Union(union_data), _) => error(
                 union_data.union_token.span(),
                 "expected one of: `struct`, `enum`",
             ),
@@ -37,13 +163,46 @@ impl ItemInfo {
 }
 
 impl StructInfo {
-    fn parse_struct(ident: &Ident, generics: &Generics, data: &DataStruct) -> Result<Self> {
+    fn parse_struct(
+        ident: &Ident,
+        vis: &Visibility,
+        generics: &Generics,
+        data: &DataStruct,
+        options: &ItemOptions,
+    ) -> Result<Self> {
         let ident = ident.clone();
-        let fields = data
-            .fields
-            .iter()
-            .map(FieldInfo::parse_field)
-            .collect::<Result<Vec<FieldInfo>>>()?;
+        let mut fields = Vec::new();
+        let mut other_field = None;
+        for field in &data.fields {
+            let attr_info = FieldAttributesInfo::new(&field.attrs)?;
+            if attr_info.other {
+                if other_field.is_some() {
+                    return error(field.span(), "only one field can be `other`");
+                }
+                other_field = Some(parse_other_field(field, &attr_info)?);
+            } else {
+                fields.push(FieldInfo::parse_field(field, attr_info)?);
+            }
+        }
+
+        if options.deny_missing {
+            for field in &mut fields {
+                field.is_required |= !field.is_optional
+                    && !field.is_union_field
+                    && !field.skip_read
+                    && field.default_override.is_none()
+                    && is_ptr_type(&field.field_type);
+            }
+        }
+
+        if let Some(other_field) = &other_field {
+            if !fields.iter().any(|f| f.is_union_field) {
+                return error(
+                    other_field.span(),
+                    "`other` fields can only be used in structs with `union_variant` fields",
+                );
+            }
+        }
 
         let generics = generics
             .params
@@ -54,22 +213,93 @@ impl StructInfo {
             })
             .collect::<Result<Vec<Ident>>>()?;
 
+        validate_unions(&fields)?;
+        validate_list_items(&fields, &generics)?;
+        if options.view {
+            validate_view_fields(&fields, other_field.as_ref())?;
+        }
+
         Ok(StructInfo {
             ident,
+            vis: vis.clone(),
             fields,
             generics,
+            other_field,
         })
     }
 }
 
 impl EnumInfo {
-    fn parse_enum(ident: &Ident, generics: &Generics, data: &DataEnum) -> Result<Self> {
+    fn parse_enum(
+        ident: &Ident,
+        generics: &Generics,
+        data: &DataEnum,
+        options: &ItemOptions,
+    ) -> Result<Self> {
         let ident = ident.clone();
-        let fields = data
-            .variants
+        let mut fields = Vec::new();
+        let mut unknown_variant = None;
+        let mut other_variant = None;
+        for variant in &data.variants {
+            let attr_info = FieldAttributesInfo::new(&variant.attrs)?;
+            if attr_info.unknown {
+                if unknown_variant.is_some() {
+                    return error(variant.span(), "only one variant can be `unknown`");
+                }
+                unknown_variant = Some(parse_ordinal_variant(variant, &attr_info, "unknown")?);
+            } else if attr_info.other {
+                if other_variant.is_some() {
+                    return error(variant.span(), "only one variant can be `other`");
+                }
+                other_variant = Some(parse_ordinal_variant(variant, &attr_info, "other")?);
+            } else {
+                fields.push(FieldInfo::parse_variant(variant, attr_info)?);
+            }
+        }
+
+        let is_union = fields
+            .iter()
+            .any(|f| !matches!(f.field_type, FieldType::EnumVariant));
+        if let Some(variant) = fields
             .iter()
-            .map(FieldInfo::parse_variant)
-            .collect::<Result<Vec<FieldInfo>>>()?;
+            .find(|f| is_union && matches!(f.field_type, FieldType::EnumVariant))
+        {
+            return error(
+                variant.rust_name.span(),
+                "enums must have only unit variants (capnp enums) or only tuple variants (capnp \
+                unions), `Void` union fields are written as `Variant(())`",
+            );
+        }
+        if is_union && fields.len() < 2 {
+            return error(
+                ident.span(),
+                "enums that represent capnp unions must have at least 2 variants",
+            );
+        }
+        match (&unknown_variant, &other_variant) {
+            (Some(unknown_variant), _) if is_union => {
+                return error(
+                    unknown_variant.span(),
+                    "`unknown` variants can only be used in enums that represent capnp enums, \
+                    use `other` for unions",
+                )
+            }
+            (_, Some(other_variant)) if !is_union => {
+                return error(
+                    other_variant.span(),
+                    "`other` variants can only be used in enums that represent capnp unions, \
+                    use `unknown` for enums",
+                )
+            }
+            _ => {}
+        }
+        if !is_union && (!options.hooks.is_empty() || options.error.is_some() || options.lenient) {
+            return error(
+                ident.span(),
+                "`lenient`, `error`, `validate`, `after_read`, and `before_write` can only be used \
+                on structs and enums that represent capnp unions",
+            );
+        }
 
         let generics = generics
             .params
@@ -80,18 +310,122 @@ impl EnumInfo {
             })
             .collect::<Result<Vec<Ident>>>()?;
 
+        validate_list_items(&fields, &generics)?;
+
         Ok(EnumInfo {
             ident,
             fields,
             generics,
+            unknown_variant,
+            other_variant,
+        })
+    }
+}
+
+impl CodedEnumInfo {
+    fn parse_coded_enum(
+        ident: &Ident,
+        generics: &Generics,
+        data: &DataEnum,
+        target: &ItemTarget,
+    ) -> Result<Self> {
+        if let Some(param) = generics.params.first() {
+            return error(
+                param.span(),
+                "`text_enum`s and `int_enum`s cannot have generics",
+            );
+        }
+
+        let mut variants = Vec::new();
+        let mut text_values: Vec<String> = Vec::new();
+        let mut int_values: Vec<i64> = Vec::new();
+        for variant in &data.variants {
+            let attr_info = FieldAttributesInfo::new(&variant.attrs)?;
+            if !matches!(variant.fields, Fields::Unit) {
+                return error(
+                    variant.span(),
+                    "`text_enum`s and `int_enum`s can only have unit variants",
+                );
+            }
+            if has_conversion_attrs(&attr_info) || attr_info.unknown || attr_info.other {
+                return error(
+                    variant.span(),
+                    "`text_enum` and `int_enum` variants can only have the `value` attribute",
+                );
+            }
+
+            match target {
+                ItemTarget::TextEnum { case_insensitive } => {
+                    let value = match &attr_info.value {
+                        Some(Expr::Lit(ExprLit {
+                            lit: Lit::Str(lit_str),
+                            ..
+                        })) => lit_str.value(),
+                        Some(expr) => {
+                            return error(expr.span(), "`text_enum` values must be strings")
+                        }
+                        None => variant.ident.to_string().to_snake_case(),
+                    };
+                    let is_duplicate = text_values.iter().any(|other| {
+                        if *case_insensitive {
+                            other.eq_ignore_ascii_case(&value)
+                        } else {
+                            *other == value
+                        }
+                    });
+                    if is_duplicate {
+                        return error(variant.span(), format!("duplicate value {value:?}"));
+                    }
+                    text_values.push(value);
+                }
+                ItemTarget::IntEnum => {
+                    let value = match (&attr_info.value, &variant.discriminant) {
+                        (Some(expr), _) | (None, Some((_, expr))) => parse_int_value(expr)?,
+                        (None, None) => match int_values.last().map(|v| v.checked_add(1)) {
+                            Some(Some(value)) => value,
+                            Some(None) => return error(variant.span(), "value overflows `i64`"),
+                            None => 0,
+                        },
+                    };
+                    if int_values.contains(&value) {
+                        return error(variant.span(), format!("duplicate value {value}"));
+                    }
+                    int_values.push(value);
+                }
+                ItemTarget::Capnp(..) => unreachable!("capnp enums are parsed by `EnumInfo`"),
+            }
+            variants.push(variant.ident.clone());
+        }
+
+        let coding = match target {
+            ItemTarget::TextEnum { case_insensitive } => EnumCoding::Text {
+                values: text_values,
+                case_insensitive: *case_insensitive,
+            },
+            ItemTarget::IntEnum => EnumCoding::Int { values: int_values },
+            ItemTarget::Capnp(..) => unreachable!("capnp enums are parsed by `EnumInfo`"),
+        };
+
+        Ok(CodedEnumInfo {
+            ident: ident.clone(),
+            variants,
+            coding,
         })
     }
 }
 
 impl FieldInfo {
-    fn parse_field(field: &Field) -> Result<Self> {
-        let attr_info = FieldAttributesInfo::new(&field.attrs)?;
-        let (field_type, field_wrapper) = FieldType::parse(&field.ty, attr_info.type_specifier)?;
+    fn parse_field(field: &Field, attr_info: FieldAttributesInfo) -> Result<Self> {
+        if attr_info.unknown {
+            return error(field.span(), "`unknown` can only be used on enum variants");
+        }
+        if attr_info.value.is_some() {
+            return error(
+                field.span(),
+                "`value` can only be used on `text_enum` and `int_enum` variants",
+            );
+        }
+        let (field_type, field_wrapper) = FieldType::parse(&field.ty, &attr_info)?;
 
         if let FieldType::Phantom = field_type {
             if attr_info.skip
@@ -100,6 +434,7 @@ impl FieldInfo {
                 || attr_info.union_field
                 || attr_info.default.is_some()
                 || attr_info.name_override.is_some()
+                || !attr_info.hooks.is_empty()
                 || !matches!(
                     attr_info.type_specifier,
                     FieldAttributeTypeSpecifier::Default
@@ -148,8 +483,9 @@ impl FieldInfo {
             todo!("`Box<T>`")
         }
 
-        Ok(FieldInfo {
+        let field_info = FieldInfo {
             rust_name: field.ident.as_ref().unwrap().clone(),
+            rust_type: field.ty.clone(),
             field_type,
             capnp_name_override: attr_info.name_override,
             has_phantom_in_variant: false,
@@ -159,13 +495,101 @@ impl FieldInfo {
             skip_read,
             skip_write,
             default_override: attr_info.default,
-        })
+            is_required: attr_info.required,
+            parallel: attr_info.parallel,
+            hooks: attr_info.hooks,
+        };
+        field_info.validate_unset_handling(field)?;
+        field_info.validate_parallel(field.span())?;
+        field_info.validate_hooks()?;
+        Ok(field_info)
+    }
+    /// Validates `default` and `required`, which handle pointer fields that are unset when read
+    fn validate_unset_handling(&self, field: &Field) -> Result<()> {
+        if let Some(default) = self.default_override.as_ref().filter(|_| !self.skip_read) {
+            if self.is_union_field {
+                return error(default.span(), "unions cannot contain default overrides");
+            }
+            if self.is_optional {
+                return error(
+                    default.span(),
+                    "optional fields are `None` when unset, so `default` with no `skip` or \
+                    `skip_read` will never be used",
+                );
+            }
+            if !is_ptr_type(&self.field_type) {
+                return error(
+                    default.span(),
+                    "`default` with no `skip` or `skip_read` is only used by pointer fields \
+                    (`List`, `Struct`, `Text`, `Data`) when they are unset",
+                );
+            }
+        }
+
+        if self.is_required
+            && (self.is_optional
+                || self.is_union_field
+                || self.skip_read
+                || self.default_override.is_some()
+                || !is_ptr_type(&self.field_type))
+        {
+            return error(
+                field.span(),
+                "`required` can only be used on pointer fields (`List`, `Struct`, `Text`, \
+                `Data`) that are not optional, union variants, skipped, or defaulted",
+            );
+        }
+
+        Ok(())
     }
-    fn parse_variant(variant: &Variant) -> Result<Self> {
+    /// Validates `parallel`, which reads lists of structs
+    fn validate_parallel(&self, span: Span) -> Result<()> {
+        if self.parallel.is_none() {
+            return Ok(());
+        }
+        if self.skip_read {
+            return error(
+                span,
+                "`parallel` is never used on fields with `skip` or `skip_read`",
+            );
+        }
+        match &self.field_type {
+            FieldType::List(item_type) if matches!(**item_type, FieldType::Struct(_)) => Ok(()),
+            _ => error(
+                span,
+                "`parallel` can only be used on lists of structs, such as `Vec<T>` of a \
+                `List(T)` field",
+            ),
+        }
+    }
+    /// Validates that the field's hooks run, they are skipped along with the field
+    fn validate_hooks(&self) -> Result<()> {
+        let read_hook = self
+            .hooks
+            .validate
+            .as_ref()
+            .or(self.hooks.after_read.as_ref());
+        match (read_hook, &self.hooks.before_write) {
+            (Some(hook), _) | (_, Some(hook)) if self.is_union_field => error(
+                hook.span(),
+                "`union_variant` fields cannot have `validate`, `after_read`, or `before_write`, \
+                put them on the struct",
+            ),
+            (Some(hook), _) if self.skip_read => error(
+                hook.span(),
+                "`validate` and `after_read` are never run on fields with `skip` or `skip_read`",
+            ),
+            (_, Some(hook)) if self.skip_write => error(
+                hook.span(),
+                "`before_write` is never run on fields with `skip` or `skip_write`",
+            ),
+            _ => Ok(()),
+        }
+    }
+    fn parse_variant(variant: &Variant, attr_info: FieldAttributesInfo) -> Result<Self> {
         let (variant_type, is_phantom) = get_variant_type(&variant.fields)?;
-        let attr_info = FieldAttributesInfo::new(&variant.attrs)?;
         let (field_type, field_wrapper) = match variant_type {
-            Some(ty) => FieldType::parse(ty, attr_info.type_specifier)?,
+            Some(ty) => FieldType::parse(ty, &attr_info)?,
             None => (FieldType::EnumVariant, FieldWrapper::None),
         };
 
@@ -184,7 +608,7 @@ impl FieldInfo {
                 )
             }
             _ => {}
-        };
+        }
 
         if let FieldWrapper::Option(ident) = field_wrapper {
             return error(ident.span(), "Enums may not have `Option<T>`");
@@ -200,6 +624,19 @@ impl FieldInfo {
                 "Enums variants cannot have `skip`, `default`, or `union_field` attributes.",
             );
         }
+        if attr_info.value.is_some() {
+            return error(
+                variant.span(),
+                "`value` can only be used on `text_enum` and `int_enum` variants",
+            );
+        }
+        if !attr_info.hooks.is_empty() {
+            return error(
+                variant.span(),
+                "Enums variants cannot have `validate`, `after_read`, or `before_write`, put them \
+                on the enum",
+            );
+        }
 
         if matches!(field_type, FieldType::EnumVariant)
             && !matches!(
@@ -219,8 +656,9 @@ impl FieldInfo {
             todo!("`Box<T>`")
         }
 
-        Ok(FieldInfo {
+        let field_info = FieldInfo {
             rust_name: variant.ident.clone(),
+            rust_type: variant_type.cloned().unwrap_or_else(|| parse_quote!(())),
             field_type,
             capnp_name_override: attr_info.name_override,
             has_phantom_in_variant: is_phantom,
@@ -230,29 +668,71 @@ impl FieldInfo {
             skip_read: false,
             skip_write: false,
             default_override: None,
-        })
+            is_required: false,
+            parallel: attr_info.parallel,
+            hooks: Hooks::default(),
+        };
+        field_info.validate_parallel(variant.span())?;
+        Ok(field_info)
     }
 }
 
 impl FieldType {
-    fn parse(ty: &Type, specifier: FieldAttributeTypeSpecifier) -> Result<(Self, FieldWrapper)> {
+    fn parse(ty: &Type, attr_info: &FieldAttributesInfo) -> Result<(Self, FieldWrapper)> {
+        let (field_type, field_wrapper) = FieldType::parse_wrapped(ty, attr_info)?;
+        if let Some(capnp_type) = &attr_info.capnp_type {
+            if !is_integer_field(&field_type) {
+                return error(
+                    capnp_type.span(),
+                    "`capnp_type` can only be used on integer and `char` fields",
+                );
+            }
+        }
+        Ok((field_type, field_wrapper))
+    }
+    fn parse_wrapped(ty: &Type, attr_info: &FieldAttributesInfo) -> Result<(Self, FieldWrapper)> {
         match try_peel_type(ty) {
             Some((ident, sub_type)) => match ident.to_string().as_str() {
                 "PhantomData" => Ok((FieldType::Phantom, FieldWrapper::None)),
                 "Option" => Ok((
-                    FieldType::parse_type(sub_type, specifier)?,
+                    FieldType::parse_type(sub_type, attr_info)?,
                     FieldWrapper::Option(ident.clone()),
                 )),
                 "Box" => Ok((
-                    FieldType::parse_type(sub_type, specifier)?,
+                    FieldType::parse_type(sub_type, attr_info)?,
                     FieldWrapper::Box(ident.clone()),
                 )),
-                _ => Ok((FieldType::parse_type(ty, specifier)?, FieldWrapper::None)),
+                _ => Ok((FieldType::parse_type(ty, attr_info)?, FieldWrapper::None)),
             },
-            None => Ok((FieldType::parse_type(ty, specifier)?, FieldWrapper::None)),
+            None => Ok((FieldType::parse_type(ty, attr_info)?, FieldWrapper::None)),
+        }
+    }
+    /// Parses the type of a field or list item, checking that its type specifier applies to it
+    fn parse_type(ty: &Type, attr_info: &FieldAttributesInfo) -> Result<Self> {
+        let field_type = FieldType::parse_specified_type(ty, attr_info)?;
+        match (attr_info.type_specifier, &field_type) {
+            (FieldAttributeTypeSpecifier::Default, _)
+            | (_, FieldType::List(_))
+            | (FieldAttributeTypeSpecifier::Data, FieldType::Data(_) | FieldType::Int128(..)) => {
+                Ok(field_type)
+            }
+            (FieldAttributeTypeSpecifier::Data, _) => error(ty.span(), DATA_TYPE_ERROR),
+            (
+                _,
+                FieldType::Void()
+                | FieldType::Primitive(_)
+                | FieldType::CheckedPrimitive(..)
+                | FieldType::Text(_),
+            ) => error(
+                ty.span(),
+                "`Void`, primitive, and `Text` fields cannot have a type specifier",
+            ),
+            _ => Ok(field_type),
         }
     }
-    fn parse_type(ty: &Type, specifier: FieldAttributeTypeSpecifier) -> Result<Self> {
+    #[allow(clippy::too_many_lines)]
+    fn parse_specified_type(ty: &Type, attr_info: &FieldAttributesInfo) -> Result<Self> {
+        let specifier = attr_info.type_specifier;
         match ty {
             Type::Tuple(tuple) if tuple.elems.is_empty() => Ok(FieldType::Void()),
             Type::Path(path) => {
@@ -263,6 +743,23 @@ impl FieldType {
                 if matches!(ident.to_string().as_str(), "Option" | "Box" | "PhantomData") {
                     // These are taken care of in before this
                     error(ident.span(), "invalid generic argument type")
+                } else if let Some(capnp_type) =
+                    checked_capnp_type(ident, attr_info.capnp_type.as_ref())
+                {
+                    Ok(FieldType::CheckedPrimitive(path.clone(), capnp_type))
+                } else if matches!(ident.to_string().as_str(), "u128" | "i128") {
+                    match specifier {
+                        FieldAttributeTypeSpecifier::Default => {
+                            Ok(FieldType::Int128(path.clone(), Int128Encoding::Words))
+                        }
+                        FieldAttributeTypeSpecifier::Data => {
+                            Ok(FieldType::Int128(path.clone(), Int128Encoding::Data))
+                        }
+                        _ => error(
+                            ident.span(),
+                            "`u128` and `i128` fields can only have the `data` type specifier",
+                        ),
+                    }
                 } else if is_capnp_primative(path) {
                     Ok(FieldType::Primitive(path.clone()))
                 } else if *ident == "String" {
@@ -281,25 +778,42 @@ impl FieldType {
                                 Ok(FieldType::EnumRemote(path.clone()))
                             }
                             FieldAttributeTypeSpecifier::Enum => Ok(FieldType::Enum(path.clone())),
+                            FieldAttributeTypeSpecifier::TextEnum => {
+                                Ok(FieldType::TextEnum(path.clone()))
+                            }
+                            FieldAttributeTypeSpecifier::IntEnum => {
+                                Ok(FieldType::IntEnum(path.clone()))
+                            }
+                            FieldAttributeTypeSpecifier::Flags(undefined_bits) => {
+                                Ok(FieldType::Flags(path.clone(), undefined_bits))
+                            }
                             FieldAttributeTypeSpecifier::GroupOrUnion => {
                                 Ok(FieldType::GroupOrUnion(path.clone()))
                             }
                             FieldAttributeTypeSpecifier::UnnamedUnion => {
                                 Ok(FieldType::UnnamedUnion(path.clone()))
                             }
-                            FieldAttributeTypeSpecifier::Data => error(
-                                ident.span(),
-                                "fields with `data` attribute must be of type `Vec<u8>`",
-                            ),
+                            FieldAttributeTypeSpecifier::Data => {
+                                error(ident.span(), DATA_TYPE_ERROR)
+                            }
                         },
                         PathArguments::AngleBracketed(args) if ident == "Vec" => {
                             match args.args.len() {
                                 1 => {
                                     let arg = args.args.first().unwrap();
                                     match arg {
-                                        GenericArgument::Type(ty) => Ok(FieldType::List(Box::new(
-                                            FieldType::parse_type(ty, specifier)?,
-                                        ))),
+                                        GenericArgument::Type(ty) => {
+                                            match FieldType::parse_type(ty, attr_info)? {
+                                                FieldType::GroupOrUnion(_)
+                                                | FieldType::UnnamedUnion(_) => error(
+                                                    ty.span(),
+                                                    "lists cannot contain groups or unions",
+                                                ),
+                                                item_type => {
+                                                    Ok(FieldType::List(Box::new(item_type)))
+                                                }
+                                            }
+                                        }
                                         _ => error(arg.span(), "invalid generic argument type"),
                                     }
                                 }
@@ -316,6 +830,7 @@ impl FieldType {
                             FieldAttributeTypeSpecifier::UnnamedUnion => {
                                 Ok(FieldType::UnnamedUnion(path.clone()))
                             }
+                            FieldAttributeTypeSpecifier::Data => error(ty.span(), DATA_TYPE_ERROR),
                             _ => error(
                                 args.span(),
                                 "generic arguments can not be specified in unions",
@@ -332,6 +847,9 @@ impl FieldType {
     }
 }
 
+const DATA_TYPE_ERROR: &str = "fields with `data` attribute must be of type `Vec<u8>`, `u128`, or \
+    `i128`";
+
 #[derive(Debug, Clone, Copy)]
 enum FieldAttributeTypeSpecifier {
     Default,
@@ -340,16 +858,27 @@ enum FieldAttributeTypeSpecifier {
     GroupOrUnion,
     UnnamedUnion,
     Data,
+    TextEnum,
+    IntEnum,
+    Flags(UndefinedBits),
 }
 
 struct FieldAttributesInfo {
     pub name_override: Option<Ident>,
     pub type_specifier: FieldAttributeTypeSpecifier,
-    pub default: Option<Path>,
+    pub default: Option<Expr>,
     pub skip: bool,
     pub skip_read: bool,
     pub skip_write: bool,
     pub union_field: bool,
+    pub unknown: bool,
+    pub other: bool,
+    pub value: Option<Expr>,
+    pub capnp_type: Option<Ident>,
+    pub required: bool,
+    /// `parallel`, with the fewest items the list is read in parallel from
+    pub parallel: Option<Expr>,
+    pub hooks: Hooks,
 }
 
 impl FieldAttributesInfo {
@@ -363,8 +892,16 @@ impl FieldAttributesInfo {
             skip_read: false,
             skip_write: false,
             union_field: false,
+            unknown: false,
+            other: false,
+            value: None,
+            capnp_type: None,
+            required: false,
+            parallel: None,
+            hooks: Hooks::default(),
         };
 
+        let mut undefined_bits = None;
         let mut processed_attrs = HashMap::new();
         for attr in attributes {
             if !is_capnp_attr(attr) {
@@ -405,21 +942,41 @@ impl FieldAttributesInfo {
               attr_info.type_specifier = FieldAttributeTypeSpecifier::Data;
               FieldAttribute::Type(meta.path.clone())
             }
+            "text_enum" => {
+              attr_info.type_specifier = FieldAttributeTypeSpecifier::TextEnum;
+              FieldAttribute::Type(meta.path.clone())
+            }
+            "int_enum" => {
+              attr_info.type_specifier = FieldAttributeTypeSpecifier::IntEnum;
+              FieldAttribute::Type(meta.path.clone())
+            }
+            "bitflags" => {
+              attr_info.type_specifier = FieldAttributeTypeSpecifier::Flags(UndefinedBits::Retain);
+              FieldAttribute::Type(meta.path.clone())
+            }
             _ => {
               return Err(meta.error(
-                "expected `enum`, `enum_remote`, `group`, `union`, `unnamed_union`, or `data`",
+                "expected `enum`, `enum_remote`, `group`, `union`, `unnamed_union`, `data`, \
+                `text_enum`, `int_enum`, or `bitflags`",
               ))
             }
           }
                 } else if meta.path.is_ident("default") {
-                    let path = meta.value()?.parse::<LitStr>()?.parse::<Path>()?;
-
-                    if path == as_turbofish(&path) {
-                        attr_info.default = Some(path.clone());
-                        FieldAttribute::Default(meta.path.clone())
+                    let value = meta.value()?;
+                    // Strings holding a path are functions to call, anything else is a value
+                    let default = if value.peek(LitStr) {
+                        let lit_str = value.parse::<LitStr>()?;
+                        match lit_str.parse::<Path>() {
+                            Ok(path) if path == as_turbofish(&path) => parse_quote!(#path()),
+                            Ok(_) => return Err(meta.error("not in turbofish format")),
+                            Err(_) => lit_str.parse::<Expr>()?,
+                        }
                     } else {
-                        return Err(meta.error("not in turbofish format"));
-                    }
+                        value.parse::<Expr>()?
+                    };
+
+                    attr_info.default = Some(default);
+                    FieldAttribute::Default(meta.path.clone())
                 } else if meta.path.is_ident("skip") {
                     attr_info.skip = true;
                     FieldAttribute::Skip(meta.path.clone())
@@ -432,10 +989,64 @@ impl FieldAttributesInfo {
                 } else if meta.path.is_ident("union_variant") {
                     attr_info.union_field = true;
                     FieldAttribute::UnionField(meta.path.clone())
+                } else if meta.path.is_ident("unknown") {
+                    attr_info.unknown = true;
+                    FieldAttribute::Unknown(meta.path.clone())
+                } else if meta.path.is_ident("other") {
+                    attr_info.other = true;
+                    FieldAttribute::Other(meta.path.clone())
+                } else if meta.path.is_ident("value") {
+                    attr_info.value = Some(meta.value()?.parse::<Expr>()?);
+                    FieldAttribute::Value(meta.path.clone())
+                } else if meta.path.is_ident("undefined_bits") {
+                    let lit_str = meta.value()?.parse::<LitStr>()?.value();
+                    undefined_bits = Some(match lit_str.as_str() {
+                        "retain" => UndefinedBits::Retain,
+                        "truncate" => UndefinedBits::Truncate,
+                        "error" => UndefinedBits::Error,
+                        _ => return Err(meta.error("expected `retain`, `truncate`, or `error`")),
+                    });
+                    FieldAttribute::UndefinedBits(meta.path.clone())
+                } else if meta.path.is_ident("capnp_type") {
+                    let lit_str = meta.value()?.parse::<LitStr>()?;
+                    let capnp_type = match lit_str.value().as_str() {
+                        "Int8" => "i8",
+                        "Int16" => "i16",
+                        "Int32" => "i32",
+                        "Int64" => "i64",
+                        "UInt8" => "u8",
+                        "UInt16" => "u16",
+                        "UInt32" => "u32",
+                        "UInt64" => "u64",
+                        _ => return Err(meta.error("expected a capnp integer type, like `UInt32`")),
+                    };
+                    attr_info.capnp_type = Some(Ident::new(capnp_type, lit_str.span()));
+                    FieldAttribute::CapnpType(meta.path.clone())
+                } else if meta.path.is_ident("required") {
+                    attr_info.required = true;
+                    FieldAttribute::Required(meta.path.clone())
+                } else if meta.path.is_ident("parallel") {
+                    let min_len = if meta.input.peek(Token![=]) {
+                        meta.value()?.parse::<Expr>()?
+                    } else {
+                        parse_quote!(::capnp_conv::PARALLEL_MIN_LEN)
+                    };
+                    attr_info.parallel = Some(min_len);
+                    FieldAttribute::Parallel(meta.path.clone())
+                } else if meta.path.is_ident("validate") {
+                    attr_info.hooks.validate = Some(parse_hook(&meta)?);
+                    FieldAttribute::Validate(meta.path.clone())
+                } else if meta.path.is_ident("after_read") {
+                    attr_info.hooks.after_read = Some(parse_hook(&meta)?);
+                    FieldAttribute::AfterRead(meta.path.clone())
+                } else if meta.path.is_ident("before_write") {
+                    attr_info.hooks.before_write = Some(parse_hook(&meta)?);
+                    FieldAttribute::BeforeWrite(meta.path.clone())
                 } else {
                     return Err(meta.error(
-                        "expected `name`, `type`, `skip`, `skip_read`, \
-                `skip_write`, `default`, or `union_variant`",
+                        "expected `name`, `type`, `skip`, `skip_read`, `skip_write`, `default`, \
+                `union_variant`, `unknown`, `other`, `value`, `undefined_bits`, `capnp_type`, \
+                `required`, `parallel`, `validate`, `after_read`, or `before_write`",
                     ));
                 };
 
@@ -454,14 +1065,15 @@ impl FieldAttributesInfo {
         // Validate
         for attr in processed_attrs.values() {
             match attr {
-                FieldAttribute::Default(ident)
-                    if !processed_attrs.values().any(|a| {
-                        matches!(a, FieldAttribute::Skip(_) | FieldAttribute::SkipRead(_))
-                    }) =>
+                FieldAttribute::UndefinedBits(ident)
+                    if !matches!(
+                        attr_info.type_specifier,
+                        FieldAttributeTypeSpecifier::Flags(_)
+                    ) =>
                 {
                     return error(
                         ident.span(),
-                        "`default` attribute with no `skip` or `skip_read` will never be used",
+                        "`undefined_bits` can only be used with `type = \"bitflags\"`",
                     )
                 }
                 FieldAttribute::Skip(ident)
@@ -481,6 +1093,12 @@ impl FieldAttributesInfo {
             }
         }
 
+        if let (FieldAttributeTypeSpecifier::Flags(_), Some(undefined_bits)) =
+            (attr_info.type_specifier, undefined_bits)
+        {
+            attr_info.type_specifier = FieldAttributeTypeSpecifier::Flags(undefined_bits);
+        }
+
         Ok(attr_info)
     }
 }
@@ -494,6 +1112,16 @@ enum FieldAttribute {
     SkipRead(Path),
     SkipWrite(Path),
     UnionField(Path),
+    Unknown(Path),
+    Other(Path),
+    Value(Path),
+    UndefinedBits(Path),
+    CapnpType(Path),
+    Required(Path),
+    Parallel(Path),
+    Validate(Path),
+    AfterRead(Path),
+    BeforeWrite(Path),
 }
 
 impl ToTokens for FieldAttribute {
@@ -506,6 +1134,16 @@ impl ToTokens for FieldAttribute {
             FieldAttribute::SkipRead(a) => tokens.extend(a.into_token_stream()),
             FieldAttribute::SkipWrite(a) => tokens.extend(a.into_token_stream()),
             FieldAttribute::UnionField(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::Unknown(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::Other(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::Value(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::UndefinedBits(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::CapnpType(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::Required(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::Parallel(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::Validate(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::AfterRead(a) => tokens.extend(a.into_token_stream()),
+            FieldAttribute::BeforeWrite(a) => tokens.extend(a.into_token_stream()),
         }
     }
 }
@@ -517,6 +1155,36 @@ fn is_capnp_primative(path: &Path) -> bool {
     )
 }
 
+/// Returns the capnp primitive that a rust integer or `char` is converted to, if it is not the
+/// rust type itself
+fn checked_capnp_type(ident: &Ident, capnp_type: Option<&Ident>) -> Option<Ident> {
+    let capnp_type = match (capnp_type, ident.to_string().as_str()) {
+        (
+            Some(capnp_type),
+            "usize" | "isize" | "char" | "u8" | "u16" | "u32" | "u64" | "i8" | "i16" | "i32"
+            | "i64",
+        ) => capnp_type.clone(),
+        (None, "usize") => Ident::new("u64", ident.span()),
+        (None, "isize") => Ident::new("i64", ident.span()),
+        (None, "char") => Ident::new("u32", ident.span()),
+        _ => return None,
+    };
+    (capnp_type != *ident).then_some(capnp_type)
+}
+
+/// Returns true if the field (or list item) is a capnp integer, so it can have a `capnp_type`
+fn is_integer_field(field_type: &FieldType) -> bool {
+    match field_type {
+        FieldType::CheckedPrimitive(..) => true,
+        FieldType::Primitive(path) => !matches!(
+            path.segments.last().unwrap().ident.to_string().as_str(),
+            "bool" | "f32" | "f64"
+        ),
+        FieldType::List(item_type) => is_integer_field(item_type),
+        _ => false,
+    }
+}
+
 /// Returns if the type signature is `Vec<u8>`, which corresponds to capnp's `Data`
 fn is_capnp_data_type(path: &Path) -> bool {
     if path.segments.last().unwrap().ident == "Vec" {
@@ -531,6 +1199,187 @@ fn is_capnp_data_type(path: &Path) -> bool {
     false
 }
 
+/// Returns true if anything besides `unknown`/`other`/`value` is set
+fn has_conversion_attrs(attr_info: &FieldAttributesInfo) -> bool {
+    attr_info.name_override.is_some()
+        || attr_info.skip
+        || attr_info.skip_read
+        || attr_info.skip_write
+        || attr_info.default.is_some()
+        || attr_info.union_field
+        || attr_info.parallel.is_some()
+        || !attr_info.hooks.is_empty()
+        || (attr_info.unknown && attr_info.other)
+        || !matches!(
+            attr_info.type_specifier,
+            FieldAttributeTypeSpecifier::Default
+        )
+}
+
+impl Hooks {
+    /// Returns the slot of the hook named by `path`
+    fn get_mut(&mut self, path: &Path) -> Option<&mut Option<Path>> {
+        if path.is_ident("validate") {
+            Some(&mut self.validate)
+        } else if path.is_ident("after_read") {
+            Some(&mut self.after_read)
+        } else if path.is_ident("before_write") {
+            Some(&mut self.before_write)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parses a hook, a string holding the path of the function to call
+fn parse_hook(meta: &ParseNestedMeta) -> Result<Path> {
+    let path = meta.value()?.parse::<LitStr>()?.parse::<Path>()?;
+    if path == as_turbofish(&path) {
+        Ok(path)
+    } else {
+        Err(meta.error("not in turbofish format"))
+    }
+}
+
+/// Validates an `unknown`/`other` variant, which must look like `Variant(u16)`
+fn parse_ordinal_variant(
+    variant: &Variant,
+    attr_info: &FieldAttributesInfo,
+    attr_name: &str,
+) -> Result<Ident> {
+    if has_conversion_attrs(attr_info) {
+        return error(
+            variant.span(),
+            format!("`{attr_name}` variants cannot have any other attributes"),
+        );
+    }
+    match &variant.fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            match &fields.unnamed.first().unwrap().ty {
+                Type::Path(path) if path.path.is_ident("u16") => Ok(variant.ident.clone()),
+                ty => error(
+                    ty.span(),
+                    format!("`{attr_name}` variants must hold a `u16`"),
+                ),
+            }
+        }
+        _ => error(
+            variant.span(),
+            format!("`{attr_name}` variants must have exactly one `u16` field"),
+        ),
+    }
+}
+
+/// Parses an `int_enum` value, which must be an integer literal
+fn parse_int_value(expr: &Expr) -> Result<i64> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit_int),
+            ..
+        }) => lit_int.base10_parse(),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => match &**expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(lit_int),
+                ..
+            }) => match format!("-{}", lit_int.base10_digits()).parse() {
+                Ok(value) => Ok(value),
+                Err(_) => error(lit_int.span(), "value overflows `i64`"),
+            },
+            _ => error(expr.span(), "`int_enum` values must be integers"),
+        },
+        _ => error(expr.span(), "`int_enum` values must be integers"),
+    }
+}
+
+/// Validates an `other` field, which must be an `Option<u16>`
+fn parse_other_field(field: &Field, attr_info: &FieldAttributesInfo) -> Result<Ident> {
+    if has_conversion_attrs(attr_info) {
+        return error(
+            field.span(),
+            "`other` fields cannot have any other attributes",
+        );
+    }
+    match try_peel_type(&field.ty) {
+        Some((ident, Type::Path(path))) if ident == "Option" && path.path.is_ident("u16") => {
+            Ok(field.ident.as_ref().unwrap().clone())
+        }
+        _ => error(field.ty.span(), "`other` fields must be `Option<u16>`"),
+    }
+}
+
+/// Validates that a struct maps to at most one unnamed union, with at least 2 fields
+fn validate_unions(fields: &[FieldInfo]) -> Result<()> {
+    let union_fields: Vec<&FieldInfo> = fields.iter().filter(|f| f.is_union_field).collect();
+    if let [union_field] = union_fields.as_slice() {
+        return error(
+            union_field.rust_name.span(),
+            "capnp unions must have at least 2 fields, add the other `union_variant` fields",
+        );
+    }
+    let mut mapped_to = (!union_fields.is_empty()).then(|| "its `union_variant` fields".to_owned());
+    for field in fields
+        .iter()
+        .filter(|f| matches!(f.field_type, FieldType::UnnamedUnion(_)))
+    {
+        if let Some(mapped_to) = &mapped_to {
+            return error(
+                field.rust_name.span(),
+                format!(
+                    "structs can only have one unnamed union, which is already mapped to \
+                     {mapped_to}"
+                ),
+            );
+        }
+        mapped_to = Some(format!("`{}`", field.rust_name));
+    }
+    Ok(())
+}
+
+/// Validates that no list holds one of the item's generic types, capnp lists need a concrete
+/// item type
+fn validate_list_items(fields: &[FieldInfo], generics: &[Ident]) -> Result<()> {
+    for field in fields {
+        let mut field_type = &field.field_type;
+        if !matches!(field_type, FieldType::List(_)) {
+            continue;
+        }
+        while let FieldType::List(item_type) = field_type {
+            field_type = item_type;
+        }
+        if let FieldType::Struct(path) = field_type {
+            if generics.iter().any(|generic| path.is_ident(generic)) {
+                return error(
+                    path.span(),
+                    "lists cannot have a generic type as their item type",
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validates that no field is named like a method of the view itself, as each field gets a view
+/// method of its name
+fn validate_view_fields(fields: &[FieldInfo], other_field: Option<&Ident>) -> Result<()> {
+    let names = fields
+        .iter()
+        .map(|field| &field.rust_name)
+        .chain(other_field);
+    for name in names {
+        if name == "new" || name == "reader" || name == "read" {
+            return error(
+                name.span(),
+                "`view` structs cannot have fields named `new`, `reader`, or `read`",
+            );
+        }
+    }
+    Ok(())
+}
+
 //returns the type of the variant's first slot, and true if the second slot contains PhntomData
 fn get_variant_type(fields: &Fields) -> Result<(Option<&Type>, bool)> {
     match fields {
@@ -556,7 +1405,7 @@ fn get_variant_type(fields: &Fields) -> Result<(Option<&Type>, bool)> {
                             "second type of an enum can only be `PhantomData<T>`",
                         )
                     }
-                };
+                }
                 Ok((Some(&fields.unnamed.first().unwrap().ty), true))
             }
             _ => error(
@@ -564,6 +1413,9 @@ fn get_variant_type(fields: &Fields) -> Result<(Option<&Type>, bool)> {
                 "enum variants may only contain 1 field (plus an optional `PhantomData`",
             ),
         },
-        Fields::Named(_) => unimplemented!(),
+        Fields::Named(fields) => error(
+            fields.span(),
+            "enum variants must be unit or tuple variants, named fields are not supported",
+        ),
     }
 }
@@ -1,7 +1,7 @@
 use std::fmt::Display;
 
-use proc_macro2::{Ident, Span};
-use quote::{format_ident, IdentFragment};
+use proc_macro2::{Group, Ident, Span, TokenStream, TokenTree};
+use quote::{format_ident, ToTokens};
 use syn::{
     token::PathSep, AttrStyle, Attribute, Error, GenericArgument, Path, PathArguments, Result, Type,
 };
@@ -17,10 +17,6 @@ pub fn is_capnp_attr(attribute: &Attribute) -> bool {
         && attribute.path().segments.last().unwrap().ident == "capnp_conv"
 }
 
-pub fn to_ident(fragment: impl IdentFragment) -> Ident {
-    format_ident!("{}", fragment)
-}
-
 pub fn to_capnp_generic(generic: &Ident) -> Ident {
     format_ident!("__CaPnP__{}", generic)
 }
@@ -40,6 +36,18 @@ pub fn try_peel_type(ty: &Type) -> Option<(&Ident, &Type)> {
     None
 }
 
+/// Whether any of `idents` is in `tokens`
+pub fn mentions_any(tokens: impl ToTokens, idents: &[Ident]) -> bool {
+    tokens
+        .into_token_stream()
+        .into_iter()
+        .any(|tree| match tree {
+            TokenTree::Ident(ident) => idents.contains(&ident),
+            TokenTree::Group(group) => mentions_any(group.stream(), idents),
+            _ => false,
+        })
+}
+
 /// Turns `Foo<T, Bar<Y>>` into `Foo::<T, Bar::<Y>>`
 pub fn as_turbofish(path: &Path) -> Path {
     let mut path = path.clone();
@@ -51,11 +59,29 @@ pub fn as_turbofish(path: &Path) -> Path {
     path
 }
 
+/// Sets the span of every token in `tokens`, so that errors in them point to `span`
+pub fn respan(tokens: impl ToTokens, span: Span) -> TokenStream {
+    tokens
+        .into_token_stream()
+        .into_iter()
+        .map(|mut tree| {
+            if let TokenTree::Group(group) = &tree {
+                tree =
+                    TokenTree::Group(Group::new(group.delimiter(), respan(group.stream(), span)));
+            }
+            tree.set_span(span);
+            tree
+        })
+        .collect()
+}
+
 pub fn is_ptr_type(field_type: &FieldType) -> bool {
     matches!(
         field_type,
         FieldType::Data(_)
             | FieldType::Text(_)
+            | FieldType::TextEnum(_)
+            | FieldType::Int128(..)
             | FieldType::Struct(_)
             | FieldType::List(_)
             | FieldType::GenericStruct(_)
@@ -0,0 +1,107 @@
+//! Compares the time and allocations of `read` and `read_into` on the same message, run with
+//! `cargo bench --bench read_into`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use capnp::message::TypedBuilder;
+use capnp_conv::{Readable, Writable};
+use read_into_rust::{Customer, Line, Order, OrderStatus, OrderTotals};
+
+use self::read_into_capnp::{order, Priority};
+
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+#[path = "../tests/read_into/read_into_capnp.rs"]
+pub mod read_into_capnp;
+#[path = "../tests/read_into/read_into_rust.rs"]
+mod read_into_rust;
+
+// The generated code refers to its module by its path in the test crate
+mod read_into {
+    pub use crate::read_into_capnp;
+}
+
+const ITERATIONS: u32 = 10_000;
+
+/// Counts the allocations of the bench, which runs on a single thread
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn order(lines: u32) -> Order {
+    Order {
+        id: 1,
+        customer: Customer {
+            name: String::from("customer"),
+            emails: vec![String::from("customer@example.com")],
+        },
+        note: String::from("note"),
+        payload: vec![1, 2, 3, 4],
+        lines: (0..lines)
+            .map(|line| Line {
+                sku: format!("sku {line}"),
+                quantity: line,
+            })
+            .collect(),
+        tags: vec![String::from("fragile")],
+        scores: (0..lines).collect(),
+        coupon: Some(String::from("coupon")),
+        status: OrderStatus::Shipped(String::from("tracking")),
+        totals: OrderTotals { net: 100, tax: 10 },
+        priority: Priority::High,
+    }
+}
+
+/// The time and allocations per iteration of `f`
+fn measure(mut f: impl FnMut()) -> (Duration, usize) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    (elapsed, allocations / ITERATIONS as usize)
+}
+
+#[allow(clippy::print_stdout)]
+fn main() {
+    for lines in [10, 1000] {
+        let input = order(lines);
+        let mut builder = TypedBuilder::<order::Owned>::new_default();
+        input.write(builder.init_root());
+        let reader = builder.get_root_as_reader().unwrap();
+
+        let (read_time, read_allocations) = measure(|| {
+            black_box(Order::read(black_box(reader)).unwrap());
+        });
+        let mut output = input.clone();
+        let (read_into_time, read_into_allocations) = measure(|| {
+            output.read_into(black_box(reader)).unwrap();
+            black_box(&output);
+        });
+
+        println!("{lines} lines:");
+        println!("  read       {read_time:>10.2?} {read_allocations:>6} allocations");
+        println!("  read_into  {read_into_time:>10.2?} {read_into_allocations:>6} allocations");
+    }
+}
@@ -0,0 +1,146 @@
+//! Tests of the bytes allocated by reads and writes, in their own test binary so the counting
+//! allocator does not replace the allocator of the other tests.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    io,
+};
+
+use capnp::{message::TypedBuilder, Word};
+use capnp_conv::{BuilderPool, Readable, Writable, WritableExt};
+use pool::pool_rust::Batch;
+use read_into::read_into_rust::{Customer, Line, Order, OrderStatus, OrderTotals};
+
+use self::read_into::read_into_capnp::{order, Priority};
+
+#[path = "pool"]
+mod pool {
+    #[allow(unused, clippy::all, clippy::pedantic)]
+    #[rustfmt::skip]
+    pub mod pool_capnp;
+    pub mod pool_rust;
+}
+
+#[path = "read_into"]
+mod read_into {
+    #[allow(unused, clippy::all, clippy::pedantic)]
+    #[rustfmt::skip]
+    pub mod read_into_capnp;
+    pub mod read_into_rust;
+}
+
+/// Counts the bytes allocated by each thread, as tests run in parallel
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + layout.size()));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocated_by(f: impl FnOnce()) -> usize {
+    let start = ALLOCATED.with(Cell::get);
+    f();
+    ALLOCATED.with(Cell::get) - start
+}
+
+fn batch(id: u64) -> Batch {
+    Batch {
+        id,
+        values: (0..1000).collect(),
+    }
+}
+
+fn order(id: u64, lines: u32) -> Order {
+    Order {
+        id,
+        customer: Customer {
+            name: format!("customer {id}"),
+            emails: vec![format!("{id}@example.com")],
+        },
+        note: format!("note {id}"),
+        payload: vec![1, 2, 3, 4],
+        lines: (0..lines)
+            .map(|line| Line {
+                sku: format!("sku {id} {line}"),
+                quantity: line,
+            })
+            .collect(),
+        tags: vec![format!("tag {id}")],
+        scores: (0..lines).collect(),
+        coupon: None,
+        status: OrderStatus::Pending(()),
+        totals: OrderTotals {
+            net: id * 100,
+            tax: id * 10,
+        },
+        priority: Priority::Low,
+    }
+}
+
+#[test]
+fn builder_pool_allocations_test() {
+    let pool = BuilderPool::default();
+    pool.write_bytes(&batch(1)).unwrap();
+
+    // The segments are reused, so only the builder's own bookkeeping is allocated
+    let input = batch(2);
+    let pooled = allocated_by(|| pool.write_to(&input, io::sink()).unwrap());
+    let unpooled = allocated_by(|| input.write_to(io::sink()).unwrap());
+    assert!(unpooled > 1000 * 8);
+    assert!(pooled < 1000, "pooled writes allocated {pooled} bytes");
+}
+
+#[test]
+fn scratch_allocations_test() {
+    let input = batch(4);
+    let mut scratch = Word::allocate_zeroed_vec(2048);
+    let scratch = Word::words_to_bytes_mut(&mut scratch);
+    let bytes = input.to_bytes().unwrap();
+
+    // Nothing but the output is allocated when the message fits in the scratch space
+    let mut output = Vec::with_capacity(bytes.len());
+    let allocated = allocated_by(|| input.write_to_with_scratch(scratch, &mut output).unwrap());
+    assert_eq!(output, bytes);
+    assert!(
+        allocated < 1000,
+        "scratch writes allocated {allocated} bytes"
+    );
+}
+
+#[test]
+fn read_into_reuses_allocations_test() {
+    let input = order(2, 4);
+    let mut message = TypedBuilder::<order::Owned>::new_default();
+    input.write(message.init_root());
+    let reader = message.get_root_as_reader().unwrap();
+
+    let mut output = order(1, 4);
+    let note = output.note.as_ptr();
+    let lines = output.lines.as_ptr();
+    let sku = output.lines[3].sku.as_ptr();
+    let emails = output.customer.emails.as_ptr();
+    let allocated = allocated_by(|| output.read_into(reader).unwrap());
+    assert_eq!(output, input);
+    assert_eq!(allocated, 0);
+    assert_eq!(output.note.as_ptr(), note);
+    assert_eq!(output.lines.as_ptr(), lines);
+    assert_eq!(output.lines[3].sku.as_ptr(), sku);
+    assert_eq!(output.customer.emails.as_ptr(), emails);
+
+    let allocated = allocated_by(|| assert_eq!(Order::read_with_path(reader).unwrap(), input));
+    assert!(allocated > 0);
+}
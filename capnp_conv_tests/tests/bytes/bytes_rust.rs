@@ -0,0 +1,12 @@
+use capnp_conv::capnp_conv;
+
+use super::bytes_capnp::record;
+
+#[capnp_conv(record)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub name: String,
+    pub values: Vec<i32>,
+    #[capnp_conv(type = "data")]
+    pub blob: Vec<u8>,
+}
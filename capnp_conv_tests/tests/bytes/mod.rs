@@ -0,0 +1,115 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod bytes_capnp;
+mod bytes_rust;
+
+use bytes_rust::Record;
+use capnp::{message::ReaderOptions, serialize, Word};
+use capnp_conv::{MessageOptions, Readable, ReadableExt, WritableExt};
+
+use crate::to_message;
+
+fn record(name: &str, len: u8) -> Record {
+    Record {
+        name: String::from(name),
+        values: (0..i32::from(len)).collect(),
+        blob: vec![7; usize::from(len)],
+    }
+}
+
+#[test]
+fn bytes_test() {
+    let input = record("bytes", 100);
+
+    let bytes = input.to_bytes().unwrap();
+    assert_eq!(Record::from_bytes(&bytes).unwrap(), input);
+
+    let packed = input.to_packed_bytes().unwrap();
+    assert!(packed.len() < bytes.len());
+    assert_eq!(Record::from_packed(packed.as_slice()).unwrap(), input);
+
+    let mut written = Vec::new();
+    input.write_to(&mut written).unwrap();
+    assert_eq!(written, bytes);
+}
+
+#[test]
+fn bytes_match_write_test() {
+    let input = record("write", 10);
+    let builder = to_message(&input);
+
+    let bytes = input.to_bytes().unwrap();
+    assert_eq!(
+        bytes,
+        serialize::write_message_to_words(builder.borrow_inner())
+    );
+    assert_eq!(
+        Record::from_bytes(&bytes).unwrap(),
+        Record::read(builder.get_root_as_reader().unwrap()).unwrap()
+    );
+}
+
+#[test]
+fn flat_slice_test() {
+    let first = record("first", 3);
+    let second = record("second", 5);
+
+    // Copied into words, so the messages are aligned
+    let bytes = [first.to_bytes().unwrap(), second.to_bytes().unwrap()].concat();
+    let mut words = Word::allocate_zeroed_vec(bytes.len() / 8);
+    Word::words_to_bytes_mut(&mut words).copy_from_slice(&bytes);
+
+    let mut slice = Word::words_to_bytes(&words);
+    assert_eq!(Record::from_flat_slice(&mut slice).unwrap(), first);
+    assert_eq!(Record::from_flat_slice(&mut slice).unwrap(), second);
+    assert!(slice.is_empty());
+}
+
+#[test]
+fn single_segment_test() {
+    let input = record("single", 100);
+
+    let segment = input.to_single_segment_bytes().unwrap();
+    let bytes = input.to_bytes().unwrap();
+    // The standard encoding adds a one word segment table
+    assert_eq!(segment.len(), bytes.len() - 8);
+    assert_eq!(Record::from_single_segment(&segment).unwrap(), input);
+
+    // Copied when it is not aligned
+    let mut unaligned = vec![0];
+    unaligned.extend(&segment);
+    assert_eq!(Record::from_single_segment(&unaligned[1..]).unwrap(), input);
+
+    let error = Record::from_single_segment(&segment[1..]).unwrap_err();
+    assert_eq!(
+        error.cause.extra,
+        format!(
+            "capnp_conv: a segment is a whole number of words, got {} bytes",
+            segment.len() - 1
+        )
+    );
+}
+
+#[test]
+fn message_options_test() {
+    let input = record("options", 100);
+    let options = MessageOptions {
+        first_segment_words: 16,
+        ..MessageOptions::default()
+    };
+
+    let error = options.write_single_segment(&input).unwrap_err();
+    assert_eq!(
+        error.extra,
+        "capnp_conv: message does not fit in a single segment of 16 words, it takes 3 segments"
+    );
+    let bytes = options.write_bytes(&input).unwrap();
+    assert_eq!(options.read_bytes::<Record>(&bytes).unwrap(), input);
+
+    let options = MessageOptions {
+        reader_options: *ReaderOptions::new().traversal_limit_in_words(Some(16)),
+        ..MessageOptions::default()
+    };
+    let bytes = input.to_single_segment_bytes().unwrap();
+    assert!(options.read_single_segment::<Record>(&bytes).is_err());
+}
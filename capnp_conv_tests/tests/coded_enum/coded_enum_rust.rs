@@ -0,0 +1,50 @@
+use capnp_conv::capnp_conv;
+
+use super::coded_enum_capnp::coded_struct;
+
+#[capnp_conv(type = "text_enum")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pending,
+    InProgress,
+    #[capnp_conv(value = "finished")]
+    Done,
+}
+
+#[capnp_conv(type = "text_enum", case_insensitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Low,
+    High,
+}
+
+#[capnp_conv(type = "int_enum")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    Ok,
+    Warn = 5,
+    Fail,
+    #[capnp_conv(value = -1)]
+    Invalid,
+}
+
+#[capnp_conv(coded_struct)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodedStruct {
+    #[capnp_conv(type = "text_enum")]
+    pub status: Status,
+    #[capnp_conv(type = "text_enum")]
+    pub level: Level,
+    #[capnp_conv(type = "int_enum")]
+    pub code: Code,
+    #[capnp_conv(type = "text_enum")]
+    pub opt_status: Option<Status>,
+    #[capnp_conv(type = "text_enum")]
+    pub statuses: Vec<Status>,
+    #[capnp_conv(type = "int_enum")]
+    pub codes: Vec<Code>,
+    #[capnp_conv(union_variant, type = "text_enum")]
+    pub union_status: Option<Status>,
+    #[capnp_conv(union_variant, type = "int_enum")]
+    pub union_code: Option<Code>,
+}
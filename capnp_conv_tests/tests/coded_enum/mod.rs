@@ -0,0 +1,101 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod coded_enum_capnp;
+mod coded_enum_rust;
+
+use capnp::message::TypedBuilder;
+use capnp_conv::{IntEnum, Readable, TextEnum, Writable};
+use coded_enum_rust::{Code, CodedStruct, Level, Status};
+
+use self::coded_enum_capnp::coded_struct;
+use crate::{assert_identical, to_message};
+
+fn sample() -> CodedStruct {
+    CodedStruct {
+        status: Status::InProgress,
+        level: Level::High,
+        code: Code::Warn,
+        opt_status: None,
+        statuses: vec![Status::Pending, Status::Done],
+        codes: vec![Code::Ok, Code::Fail, Code::Invalid],
+        union_status: None,
+        union_code: Some(Code::Fail),
+    }
+}
+
+#[test]
+fn coded_enum_test() {
+    assert_identical(&sample());
+    assert_identical(&CodedStruct {
+        opt_status: Some(Status::Done),
+        union_status: Some(Status::Pending),
+        union_code: None,
+        ..sample()
+    });
+}
+
+#[test]
+fn coded_values_test() {
+    assert_eq!(Status::InProgress.to_text(), "in_progress");
+    assert_eq!(Status::Done.to_text(), "finished");
+    assert_eq!(Level::High.to_text(), "high");
+    assert_eq!(Code::Ok.to_int(), 0);
+    assert_eq!(Code::Fail.to_int(), 6);
+    assert_eq!(Code::Invalid.to_int(), -1);
+
+    let builder = to_message(&sample());
+    let reader = builder.get_root_as_reader().unwrap();
+    assert_eq!(reader.get_status().unwrap(), "in_progress");
+    assert_eq!(reader.get_code(), 5);
+    assert_eq!(reader.get_codes().unwrap().get(2), -1);
+}
+
+#[test]
+fn case_insensitive_test() {
+    assert_eq!(Level::from_text("HiGh").unwrap(), Level::High);
+    assert!(Status::from_text("PENDING").is_err());
+}
+
+#[test]
+fn unknown_value_test() {
+    let mut builder = to_message(&sample());
+    builder.get_root().unwrap().set_status("cancelled");
+    let error = CodedStruct::read(builder.get_root_as_reader().unwrap()).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("unknown `Status` value \"cancelled\""));
+
+    builder.get_root().unwrap().set_status("pending");
+    builder.get_root().unwrap().set_code(9);
+    let error = CodedStruct::read(builder.get_root_as_reader().unwrap()).unwrap_err();
+    assert!(error.to_string().contains("unknown `Code` code 9"));
+}
+
+#[test]
+fn code_out_of_range_test() {
+    let input = CodedStruct {
+        code: Code::Invalid,
+        ..sample()
+    };
+    let mut builder = TypedBuilder::<coded_struct::Owned>::new_default();
+    let error = input.try_write(builder.init_root()).unwrap_err();
+    assert!(error.extra.contains("does not fit in its field"));
+}
+
+#[test]
+#[should_panic(expected = "int enum code -1 does not fit in its field, use `try_write`")]
+fn code_out_of_range_write_test() {
+    to_message(&CodedStruct {
+        code: Code::Invalid,
+        ..sample()
+    });
+}
+
+#[test]
+#[should_panic(expected = "int enum code -1 does not fit in its field, use `try_write`")]
+fn union_code_out_of_range_write_test() {
+    to_message(&CodedStruct {
+        union_code: Some(Code::Invalid),
+        ..sample()
+    });
+}
@@ -50,6 +50,7 @@ pub struct BasicStruct {
 
 #[capnp_conv(comprehensive_struct::nested_struct)]
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(dead_code)]
 pub struct NestedStruct<T, Y> {
     pub t_val: T,
     pub y_val: Y,
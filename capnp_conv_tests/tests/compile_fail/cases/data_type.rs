@@ -0,0 +1,27 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::text)]
+pub struct Text {
+    #[capnp_conv(type = "data")]
+    pub val: String,
+}
+
+#[capnp_conv(schema::list)]
+pub struct List {
+    #[capnp_conv(type = "data")]
+    pub val: Vec<i32>,
+}
+
+#[capnp_conv(schema::nested)]
+pub struct Nested {
+    #[capnp_conv(type = "data")]
+    pub val: Inner,
+}
+
+#[capnp_conv(schema::generic)]
+pub struct Generic {
+    #[capnp_conv(type = "data")]
+    pub val: Inner<u32>,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use capnp_conv::capnp_conv;
+
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+#[path = "../spans/spans_capnp.rs"]
+pub mod spans_capnp;
+
+// The generated code refers to its module by its path in the test crate
+mod compile_fail {
+    pub mod spans {
+        pub use crate::spans_capnp;
+    }
+}
+
+use compile_fail::spans::spans_capnp::{point, Kind};
+
+#[capnp_conv(point)]
+pub struct Point {
+    pub x: i32,
+    pub kind: Kind,
+}
+
+fn main() {}
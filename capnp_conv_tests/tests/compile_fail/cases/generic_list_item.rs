@@ -0,0 +1,14 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::holder)]
+pub struct Holder<T> {
+    pub items: Vec<T>,
+}
+
+#[capnp_conv(schema::nested_holder)]
+pub enum NestedHolder<T> {
+    Items(Vec<Vec<T>>),
+    Empty(()),
+}
+
+fn main() {}
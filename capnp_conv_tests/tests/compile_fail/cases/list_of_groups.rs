@@ -0,0 +1,9 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::outer)]
+pub struct Outer {
+    #[capnp_conv(type = "group")]
+    pub groups: Vec<Group>,
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::outer)]
+pub struct Outer {
+    #[capnp_conv(type = "unnamed_union")]
+    pub first: First,
+    #[capnp_conv(type = "unnamed_union")]
+    pub second: Second,
+}
+
+#[capnp_conv(schema::outer)]
+pub struct Mixed {
+    #[capnp_conv(union_variant)]
+    pub number: Option<i32>,
+    #[capnp_conv(union_variant)]
+    pub word: Option<String>,
+    #[capnp_conv(type = "unnamed_union")]
+    pub first: First,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::choice)]
+pub enum Choice {
+    Number { value: i32 },
+    Word(String),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::dataset)]
+pub struct Dataset {
+    #[capnp_conv(parallel)]
+    pub values: Vec<u32>,
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::primitive)]
+pub struct Primitive {
+    #[capnp_conv(type = "enum")]
+    pub val: u32,
+}
+
+#[capnp_conv(schema::text)]
+pub struct Text {
+    #[capnp_conv(type = "group")]
+    pub val: String,
+}
+
+#[capnp_conv(schema::void)]
+pub struct Void {
+    #[capnp_conv(type = "enum_remote")]
+    pub val: (),
+}
+
+#[capnp_conv(schema::list)]
+pub struct List {
+    #[capnp_conv(type = "text_enum")]
+    pub val: Vec<i16>,
+}
+
+fn main() {}
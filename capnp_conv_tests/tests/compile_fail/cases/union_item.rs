@@ -0,0 +1,9 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::number)]
+pub union Number {
+    pub int: i32,
+    pub float: f32,
+}
+
+fn main() {}
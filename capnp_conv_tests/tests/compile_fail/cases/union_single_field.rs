@@ -0,0 +1,10 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::choice)]
+pub struct Choice {
+    pub name: String,
+    #[capnp_conv(union_variant)]
+    pub number: Option<i32>,
+}
+
+fn main() {}
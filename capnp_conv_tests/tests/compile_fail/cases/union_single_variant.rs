@@ -0,0 +1,8 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::choice)]
+pub enum Choice {
+    Number(i32),
+}
+
+fn main() {}
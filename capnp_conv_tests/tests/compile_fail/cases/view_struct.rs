@@ -0,0 +1,13 @@
+use capnp_conv::capnp_conv;
+
+#[capnp_conv(schema::generic_box, view)]
+pub struct GenericBox<T> {
+    pub value: T,
+}
+
+#[capnp_conv(schema::message, view)]
+pub struct Message {
+    pub read: bool,
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[test]
+fn compile_fail_test() {
+    trybuild::TestCases::new().compile_fail("tests/compile_fail/cases/*.rs");
+}
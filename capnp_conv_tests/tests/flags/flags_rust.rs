@@ -0,0 +1,38 @@
+use bitflags::bitflags;
+use capnp_conv::capnp_conv;
+
+use super::flags_capnp::flags_struct;
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Permissions: u32 {
+        const READ = 1;
+        const WRITE = 1 << 1;
+        const EXECUTE = 1 << 2;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Features: u64 {
+        const COMPRESSION = 1;
+        const ENCRYPTION = 1 << 40;
+    }
+}
+
+#[capnp_conv(flags_struct)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlagsStruct {
+    #[capnp_conv(type = "bitflags")]
+    pub permissions: Permissions,
+    #[capnp_conv(type = "bitflags")]
+    pub features: Features,
+    #[capnp_conv(type = "bitflags")]
+    pub permission_list: Vec<Permissions>,
+    #[capnp_conv(type = "bitflags", undefined_bits = "truncate")]
+    pub truncated: Permissions,
+    #[capnp_conv(type = "bitflags", undefined_bits = "error")]
+    pub strict: Permissions,
+    #[capnp_conv(type = "bitflags")]
+    pub opt_permissions: Option<Permissions>,
+}
@@ -0,0 +1,60 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod flags_capnp;
+mod flags_rust;
+
+use capnp_conv::Readable;
+use flags_rust::{Features, FlagsStruct, Permissions};
+
+use crate::{assert_identical, to_message};
+
+fn sample() -> FlagsStruct {
+    FlagsStruct {
+        permissions: Permissions::READ | Permissions::EXECUTE,
+        features: Features::ENCRYPTION,
+        permission_list: vec![Permissions::empty(), Permissions::all()],
+        truncated: Permissions::WRITE,
+        strict: Permissions::READ,
+        opt_permissions: Some(Permissions::WRITE),
+    }
+}
+
+#[test]
+fn flags_test() {
+    assert_identical(&sample());
+
+    let builder = to_message(&sample());
+    let reader = builder.get_root_as_reader().unwrap();
+    assert_eq!(reader.get_permissions(), 0b101);
+    assert_eq!(reader.get_features(), 1 << 40);
+}
+
+#[test]
+fn undefined_bits_test() {
+    let mut builder = to_message(&sample());
+    let mut root = builder.get_root().unwrap();
+    root.set_permissions(0b1001);
+    root.set_truncated(0b1010);
+    root.get_permission_list().unwrap().set(0, 0b1000);
+
+    let output = FlagsStruct::read(builder.get_root_as_reader().unwrap()).unwrap();
+    assert_eq!(output.permissions.bits(), 0b1001);
+    assert_eq!(output.truncated, Permissions::WRITE);
+    assert_eq!(output.permission_list[0].bits(), 0b1000);
+
+    // Retained bits are written back unchanged
+    let builder = to_message(&output);
+    assert_eq!(
+        builder.get_root_as_reader().unwrap().get_permissions(),
+        0b1001
+    );
+}
+
+#[test]
+fn undefined_bits_error_test() {
+    let mut builder = to_message(&sample());
+    builder.get_root().unwrap().set_strict(0b1_0001);
+
+    let error = FlagsStruct::read(builder.get_root_as_reader().unwrap()).unwrap_err();
+    assert!(error.to_string().contains("undefined bits 0x10 set in"));
+}
@@ -0,0 +1,148 @@
+use std::fmt::{self, Display, Formatter};
+
+use capnp_conv::capnp_conv;
+
+use super::hooks_capnp::{account, ledger, shape, user};
+
+#[capnp_conv(
+    user,
+    validate = "Self::validate",
+    after_read = "Self::normalized",
+    before_write = "Self::to_normalized"
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    #[capnp_conv(after_read = "trimmed", before_write = "to_trimmed")]
+    pub name: String,
+    pub email: String,
+    #[capnp_conv(validate = "validate_age")]
+    pub age: u8,
+    #[capnp_conv(before_write = "to_trimmed_opt", validate = "validate_nickname")]
+    pub nickname: Option<String>,
+}
+
+impl User {
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.name.is_empty() {
+            Err("name is empty")
+        } else {
+            Ok(())
+        }
+    }
+
+    fn normalized(self) -> Self {
+        Self {
+            email: self.email.to_lowercase(),
+            ..self
+        }
+    }
+
+    fn to_normalized(&self) -> Self {
+        self.clone().normalized()
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn trimmed(name: String) -> String {
+    name.trim().to_owned()
+}
+
+#[allow(clippy::ptr_arg)]
+fn to_trimmed(name: &String) -> String {
+    name.trim().to_owned()
+}
+
+#[allow(clippy::ref_option)]
+fn to_trimmed_opt(nickname: &Option<String>) -> Option<String> {
+    nickname.as_deref().map(str::trim).map(str::to_owned)
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn validate_age(age: &u8) -> Result<(), String> {
+    if *age <= 150 {
+        Ok(())
+    } else {
+        Err(format!("{age} is not a plausible age"))
+    }
+}
+
+#[allow(clippy::ref_option)]
+fn validate_nickname(nickname: &Option<String>) -> Result<(), &'static str> {
+    match nickname {
+        Some(nickname) if nickname.contains(' ') => Err("nicknames are one word"),
+        _ => Ok(()),
+    }
+}
+
+#[capnp_conv(shape, validate = "Shape::validate")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Circle(f64),
+    Square(f64),
+}
+
+impl Shape {
+    fn validate(&self) -> Result<(), &'static str> {
+        match self {
+            Shape::Circle(size) | Shape::Square(size) if *size < 0.0 => Err("size is negative"),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AccountError {
+    Capnp(String),
+    Overdrawn(i64),
+    Unnamed,
+}
+
+impl From<capnp::Error> for AccountError {
+    fn from(error: capnp::Error) -> Self {
+        AccountError::Capnp(error.to_string())
+    }
+}
+
+impl Display for AccountError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountError::Capnp(message) => write!(f, "{message}"),
+            AccountError::Overdrawn(balance) => write!(f, "overdrawn by {}", -balance),
+            AccountError::Unnamed => write!(f, "account has no nickname"),
+        }
+    }
+}
+
+#[capnp_conv(account, error = "AccountError", validate = "Self::validate")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub user: User,
+    #[capnp_conv(validate = "validate_balance")]
+    pub balance: i64,
+}
+
+impl Account {
+    fn validate(&self) -> Result<(), AccountError> {
+        if self.user.nickname.is_none() {
+            Err(AccountError::Unnamed)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn validate_balance(balance: &i64) -> Result<(), AccountError> {
+    if *balance < 0 {
+        Err(AccountError::Overdrawn(*balance))
+    } else {
+        Ok(())
+    }
+}
+
+/// Keeps `capnp::Error`, holding an item with another error type
+#[capnp_conv(ledger)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ledger {
+    pub account: Account,
+}
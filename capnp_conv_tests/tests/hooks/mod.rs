@@ -0,0 +1,147 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod hooks_capnp;
+mod hooks_rust;
+
+use capnp_conv::Readable;
+use hooks_rust::{Account, AccountError, Ledger, Shape, User};
+
+use self::hooks_capnp::user;
+use crate::{assert_identical, assert_match, to_message};
+
+fn sample() -> User {
+    User {
+        name: String::from("Ann"),
+        email: String::from("ann@example.com"),
+        age: 30,
+        nickname: Some(String::from("annie")),
+    }
+}
+
+fn read_user(edit: impl FnOnce(user::Builder)) -> capnp::Result<User> {
+    let mut builder = to_message(&sample());
+    edit(builder.get_root().unwrap());
+    User::read(builder.get_root_as_reader().unwrap())
+}
+
+#[test]
+fn hooks_test() {
+    assert_identical(&sample());
+
+    let input = User {
+        name: String::from("  Ann "),
+        email: String::from("Ann@Example.COM"),
+        nickname: Some(String::from(" annie ")),
+        ..sample()
+    };
+    let builder = to_message(&input);
+    let reader = builder.get_root_as_reader().unwrap();
+    assert_eq!(reader.get_name().unwrap(), "Ann");
+    assert_eq!(reader.get_email().unwrap(), "ann@example.com");
+    assert_eq!(reader.get_nickname().unwrap(), "annie");
+    assert_match(&input, &sample());
+
+    let output = read_user(|mut root| {
+        root.set_name("\tAnn\n");
+        root.set_email("ANN@EXAMPLE.COM");
+    });
+    assert_eq!(output.unwrap(), sample());
+}
+
+#[test]
+fn validate_test() {
+    let error = read_user(|mut root| root.set_age(200)).unwrap_err();
+    assert!(error.to_string().contains(
+        "capnp_conv: field `age` (capnp `age`) failed validation: 200 is not a plausible age"
+    ));
+
+    let error = read_user(|mut root| root.set_nickname("ann marie")).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("field `nickname` (capnp `nickname`) failed validation: nicknames are one word"));
+
+    // `after_read` runs first, so the trimmed name is validated
+    let error = read_user(|mut root| root.set_name("   ")).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("capnp_conv: `User` failed validation: name is empty"));
+
+    assert_identical(&Shape::Square(2.0));
+    let builder = to_message(&Shape::Circle(-1.0));
+    let error = Shape::read(builder.get_root_as_reader().unwrap()).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("`Shape` failed validation: size is negative"));
+}
+
+#[test]
+fn typed_error_test() {
+    let account = Account {
+        user: sample(),
+        balance: 10,
+    };
+    assert_identical(&account);
+
+    let overdrawn = to_message(&Account {
+        balance: -5,
+        ..account.clone()
+    });
+    let reader = overdrawn.get_root_as_reader().unwrap();
+    assert_eq!(Account::try_from(reader), Err(AccountError::Overdrawn(-5)));
+    assert!(Account::read(reader)
+        .unwrap_err()
+        .extra
+        .contains("overdrawn by 5 (at `Account.balance`)"));
+    let error = Account::read_with_path(reader).unwrap_err();
+    assert_eq!(error.path_string(), "Account.balance");
+    assert_eq!(error.cause, AccountError::Overdrawn(-5));
+
+    let unnamed = to_message(&Account {
+        user: User {
+            nickname: None,
+            ..sample()
+        },
+        ..account.clone()
+    });
+    let reader = unnamed.get_root_as_reader().unwrap();
+    assert_eq!(Account::try_from(reader), Err(AccountError::Unnamed));
+
+    // Errors of nested items are converted with `From`
+    let old = to_message(&Account {
+        user: User {
+            age: 200,
+            ..sample()
+        },
+        ..account
+    });
+    let error = Account::read_with_path(old.get_root_as_reader().unwrap()).unwrap_err();
+    assert_eq!(error.path_string(), "Account.user.age");
+    assert!(
+        matches!(error.cause, AccountError::Capnp(message) if message.contains("200 is not a plausible age"))
+    );
+}
+
+#[test]
+fn nested_typed_error_test() {
+    let ledger = Ledger {
+        account: Account {
+            user: sample(),
+            balance: 10,
+        },
+    };
+    assert_identical(&ledger);
+
+    let builder = to_message(&Ledger {
+        account: Account {
+            balance: -5,
+            ..ledger.account
+        },
+    });
+    let reader = builder.get_root_as_reader().unwrap();
+
+    // The `AccountError` is formatted into the `capnp::Error` of `Ledger`
+    let error = Ledger::read_with_path(reader).unwrap_err();
+    assert_eq!(error.path_string(), "Ledger.account.balance");
+    assert_eq!(error.cause.extra, "overdrawn by 5");
+    assert!(Ledger::try_from(reader).is_err());
+}
@@ -0,0 +1,17 @@
+use capnp_conv::capnp_conv;
+
+use super::lazy_list_capnp::{catalog, item};
+
+#[capnp_conv(catalog)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    pub items: Vec<Item>,
+}
+
+#[capnp_conv(item)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub name: String,
+    #[capnp_conv(capnp_type = "UInt32")]
+    pub price: u16,
+}
@@ -0,0 +1,70 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod lazy_list_capnp;
+mod lazy_list_rust;
+
+use capnp::message::Builder;
+use capnp_conv::LazyList;
+use lazy_list_capnp::catalog;
+use lazy_list_rust::{Catalog, Item};
+
+use crate::to_message;
+
+fn item(idx: u16) -> Item {
+    Item {
+        name: format!("item {idx}"),
+        price: idx * 10,
+    }
+}
+
+#[test]
+fn lazy_list_test() {
+    let input = Catalog {
+        items: (0..100).map(item).collect(),
+    };
+    let message = to_message(&input);
+    let root = message.get_root_as_reader().unwrap();
+
+    let items = LazyList::<Item>::new(root.get_items().unwrap());
+    assert_eq!(items.len(), 100);
+    assert!(!items.is_empty());
+    assert_eq!(items.get(42).unwrap().unwrap(), item(42));
+    assert!(items.get(100).is_none());
+
+    let output: Vec<Item> = items.iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(output, input.items);
+
+    let mut iter = items.into_iter();
+    assert_eq!(iter.len(), 100);
+    assert_eq!(iter.next_back().unwrap().unwrap(), item(99));
+    assert_eq!(iter.nth(9).unwrap().unwrap(), item(9));
+    assert_eq!(iter.len(), 89);
+}
+
+#[test]
+fn lazy_list_error_test() {
+    let mut message = Builder::new_default();
+    let mut items = message.init_root::<catalog::Builder<'_>>().init_items(3);
+    for idx in 0..3 {
+        let mut item = items.reborrow().get(idx);
+        item.set_name(format!("item {idx}"));
+        item.set_price(idx * 10);
+    }
+    items.reborrow().get(1).set_price(u32::MAX);
+    let root = message.get_root_as_reader::<catalog::Reader<'_>>().unwrap();
+
+    // Only the items that are read fail
+    let items = LazyList::<Item>::new(root.get_items().unwrap());
+    assert_eq!(items.get(2).unwrap().unwrap(), item(2));
+    let error = items.get(1).unwrap().unwrap_err();
+    assert_eq!(error.path_string(), "Item[1].price");
+
+    let results: Vec<_> = items.iter().map(|item| item.is_ok()).collect();
+    assert_eq!(results, [true, false, true]);
+    let error: capnp::Error = items
+        .iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err()
+        .into();
+    assert!(error.extra.contains("price"));
+}
@@ -0,0 +1,68 @@
+use capnp_conv::capnp_conv;
+
+use super::lenient_capnp::{boxed, inner, outer};
+
+#[capnp_conv(inner, lenient)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inner {
+    #[capnp_conv(capnp_type = "UInt16")]
+    pub code: u8,
+    pub label: String,
+}
+
+/// Maps to the same capnp struct as `Inner`, without `lenient`
+#[capnp_conv(inner)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StrictInner {
+    #[capnp_conv(capnp_type = "UInt16")]
+    pub code: u8,
+    pub label: String,
+}
+
+#[capnp_conv(outer, lenient)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outer {
+    #[capnp_conv(default = "\"unnamed\"")]
+    pub name: String,
+    #[capnp_conv(capnp_type = "UInt16")]
+    pub count: u8,
+    pub inner: Inner,
+    pub inners: Vec<Inner>,
+    pub strict: StrictInner,
+    #[capnp_conv(required)]
+    pub tag: String,
+    #[capnp_conv(type = "union")]
+    pub choice: OuterChoice,
+}
+
+#[capnp_conv(outer::choice, lenient)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OuterChoice {
+    #[capnp_conv(capnp_type = "UInt16")]
+    Number(u8),
+    Word(String),
+}
+
+impl Default for OuterChoice {
+    fn default() -> Self {
+        OuterChoice::Number(0)
+    }
+}
+
+/// The generic parameter is not required to implement `Default`
+#[capnp_conv(boxed, lenient)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Boxed<T> {
+    pub value: T,
+    #[capnp_conv(capnp_type = "UInt16")]
+    pub count: u8,
+}
+
+/// Has no `Default`
+#[capnp_conv(inner)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Code {
+    #[capnp_conv(capnp_type = "UInt16")]
+    pub code: u8,
+    pub label: String,
+}
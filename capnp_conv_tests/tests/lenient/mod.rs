@@ -0,0 +1,145 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod lenient_capnp;
+mod lenient_rust;
+
+use capnp::{dynamic_struct, dynamic_value, message::TypedBuilder, text};
+use capnp_conv::{ReadError, Readable};
+use lenient_rust::{Boxed, Code, Inner, Outer, OuterChoice, StrictInner};
+
+use self::lenient_capnp::{inner, outer};
+use crate::{assert_identical, to_message, INVALID_UTF8};
+
+fn sample() -> Outer {
+    let inner = Inner {
+        code: 1,
+        label: String::from("one"),
+    };
+    Outer {
+        name: String::from("outer"),
+        count: 2,
+        inner: inner.clone(),
+        inners: vec![inner.clone(), inner],
+        strict: StrictInner {
+            code: 3,
+            label: String::from("three"),
+        },
+        tag: String::from("tag"),
+        choice: OuterChoice::Word(String::from("word")),
+    }
+}
+
+fn read_lenient(edit: impl FnOnce(outer::Builder)) -> (Outer, Vec<String>) {
+    let mut builder = to_message(&sample());
+    edit(builder.get_root().unwrap());
+    let (value, errors) = Outer::read_lenient(builder.get_root_as_reader().unwrap()).unwrap();
+    (value, errors.iter().map(ReadError::path_string).collect())
+}
+
+fn break_inner(mut inner: inner::Builder) {
+    inner.set_code(300);
+    inner.set_label(text::Reader::from(INVALID_UTF8));
+}
+
+#[test]
+fn lenient_test() {
+    assert_identical(&sample());
+    assert_eq!(read_lenient(|_| {}), (sample(), vec![]));
+}
+
+#[test]
+fn lenient_field_errors_test() {
+    let (value, errors) = read_lenient(|mut root| {
+        root.set_name(text::Reader::from(INVALID_UTF8));
+        root.set_count(300);
+        break_inner(root.reborrow().get_inner().unwrap());
+        break_inner(root.reborrow().get_inners().unwrap().get(1));
+        root.reborrow().get_strict().unwrap().set_code(300);
+        dynamic_value::Builder::from(root.reborrow())
+            .downcast::<dynamic_struct::Builder>()
+            .clear_named("tag")
+            .unwrap();
+        root.get_choice().set_word(text::Reader::from(INVALID_UTF8));
+    });
+    assert_eq!(
+        errors,
+        vec![
+            "Outer.name",
+            "Outer.count",
+            "Outer.inner.code",
+            "Outer.inner.label",
+            "Outer.inners[1].code",
+            "Outer.inners[1].label",
+            "Outer.strict.code",
+            "Outer.tag",
+            "Outer.choice.Word",
+        ]
+    );
+    assert_eq!(
+        value,
+        Outer {
+            name: String::from("unnamed"),
+            count: 0,
+            inner: Inner::default(),
+            inners: vec![sample().inner, Inner::default()],
+            strict: StrictInner::default(),
+            tag: String::new(),
+            choice: OuterChoice::Word(String::new()),
+        }
+    );
+}
+
+#[test]
+fn lenient_error_causes_test() {
+    let mut builder = to_message(&sample());
+    builder.get_root().unwrap().set_count(300);
+    let reader = builder.get_root_as_reader().unwrap();
+
+    let (_, errors) = Outer::read_lenient(reader).unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]
+        .cause
+        .extra
+        .contains("`count` value 300 is out of range for `u8`"));
+    assert!(Outer::read(reader).is_err());
+}
+
+#[test]
+fn lenient_item_error_test() {
+    let mut builder = TypedBuilder::<inner::Owned>::new_default();
+    let mut root = builder.init_root();
+    root.set_code(300);
+    let reader = builder.get_root_as_reader().unwrap();
+
+    let error = StrictInner::read_lenient(reader).unwrap_err();
+    assert_eq!(error.path_string(), "StrictInner.code");
+    let (value, errors) = Inner::read_lenient(reader).unwrap();
+    assert_eq!(value, Inner::default());
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn lenient_generic_test() {
+    let mut builder = to_message(&Boxed {
+        value: Code {
+            code: 1,
+            label: String::from("one"),
+        },
+        count: 2,
+    });
+    builder.get_root().unwrap().set_count(300);
+    let (value, errors) =
+        Boxed::<Code>::read_lenient(builder.get_root_as_reader().unwrap()).unwrap();
+    assert_eq!(value.count, 0);
+    assert_eq!(errors.len(), 1);
+
+    // Without a `Default` for the generic field, its failure fails the read
+    builder
+        .get_root()
+        .unwrap()
+        .get_value()
+        .unwrap()
+        .set_code(300);
+    let error = Boxed::<Code>::read_lenient(builder.get_root_as_reader().unwrap()).unwrap_err();
+    assert_eq!(error.path_string(), "Boxed.value.code");
+}
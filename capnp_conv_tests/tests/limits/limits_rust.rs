@@ -0,0 +1,12 @@
+use capnp_conv::capnp_conv;
+
+use super::limits_capnp::node;
+
+#[capnp_conv(node)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub name: String,
+    #[capnp_conv(type = "data")]
+    pub blob: Vec<u8>,
+    pub children: Vec<Node>,
+}
@@ -0,0 +1,151 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod limits_capnp;
+mod limits_rust;
+
+use capnp_conv::{ReadContext, ReadError, ReadLimits, Readable};
+use limits_rust::Node;
+
+use crate::to_message;
+
+fn leaf(name: &str) -> Node {
+    Node {
+        name: String::from(name),
+        blob: vec![1, 2, 3],
+        children: vec![],
+    }
+}
+
+/// Three levels deep, with two children at the root
+fn sample() -> Node {
+    Node {
+        children: vec![
+            Node {
+                children: vec![leaf("grandchild")],
+                ..leaf("child")
+            },
+            leaf("other"),
+        ],
+        ..leaf("root")
+    }
+}
+
+fn read_limited(limits: ReadLimits) -> Result<Node, ReadError> {
+    let builder = to_message(&sample());
+    Node::read_with_limits(builder.get_root_as_reader().unwrap(), limits)
+}
+
+fn limit_error(limits: ReadLimits) -> (String, String) {
+    let error = read_limited(limits).unwrap_err();
+    (error.path_string(), error.cause.extra)
+}
+
+#[test]
+fn limits_test() {
+    assert_eq!(read_limited(ReadLimits::default()).unwrap(), sample());
+    assert_eq!(
+        read_limited(ReadLimits {
+            max_list_len: 2,
+            max_text_len: 10,
+            max_data_len: 3,
+            max_depth: 3,
+            max_total_bytes: 1024,
+        })
+        .unwrap(),
+        sample()
+    );
+}
+
+#[test]
+fn total_bytes_test() {
+    let builder = to_message(&sample());
+    let reader = builder.get_root_as_reader().unwrap();
+
+    let mut context = ReadContext::new(ReadLimits::default());
+    Node::read_in_context(reader, &mut context).unwrap();
+    let node_lists = 3 * std::mem::size_of::<Node>() as u64;
+    let text = ["root", "child", "grandchild", "other"].concat().len() as u64;
+    let total_bytes = node_lists + text + 4 * 3;
+    assert_eq!(context.total_bytes(), total_bytes);
+
+    let error = Node::read_with_limits(
+        reader,
+        ReadLimits {
+            max_total_bytes: total_bytes - 1,
+            ..ReadLimits::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(error.path_string(), "Node.children[1].blob");
+    assert_eq!(
+        error.cause.extra,
+        format!(
+            "capnp_conv: `blob` takes the read past its limit of {} bytes",
+            total_bytes - 1
+        )
+    );
+}
+
+#[test]
+fn limit_errors_test() {
+    assert_eq!(
+        limit_error(ReadLimits {
+            max_list_len: 1,
+            ..ReadLimits::default()
+        }),
+        (
+            String::from("Node.children"),
+            String::from("capnp_conv: `children` has 2 elements, more than the limit of 1")
+        )
+    );
+    assert_eq!(
+        limit_error(ReadLimits {
+            max_text_len: 9,
+            ..ReadLimits::default()
+        }),
+        (
+            String::from("Node.children[0].children[0].name"),
+            String::from("capnp_conv: `name` has 10 bytes of text, more than the limit of 9")
+        )
+    );
+    assert_eq!(
+        limit_error(ReadLimits {
+            max_data_len: 2,
+            ..ReadLimits::default()
+        }),
+        (
+            String::from("Node.blob"),
+            String::from("capnp_conv: `blob` has 3 bytes of data, more than the limit of 2")
+        )
+    );
+    assert_eq!(
+        limit_error(ReadLimits {
+            max_depth: 2,
+            ..ReadLimits::default()
+        }),
+        (
+            String::from("Node.children[0].children[0]"),
+            String::from("capnp_conv: `Node` is nested more than 2 deep")
+        )
+    );
+}
+
+#[test]
+fn failed_enter_test() {
+    let builder = to_message(&leaf("leaf"));
+    let reader = builder.get_root_as_reader().unwrap();
+
+    let mut context = ReadContext::new(ReadLimits {
+        max_depth: 1,
+        ..ReadLimits::default()
+    });
+    context.enter("Outer").unwrap();
+    // Reads that fail to enter leave the depth as it was
+    Node::read_in_context(reader, &mut context).unwrap_err();
+    Node::read_in_context(reader, &mut context).unwrap_err();
+    context.exit();
+    assert_eq!(
+        Node::read_in_context(reader, &mut context).unwrap(),
+        leaf("leaf")
+    );
+}
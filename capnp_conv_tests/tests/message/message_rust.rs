@@ -0,0 +1,16 @@
+use capnp_conv::capnp_conv;
+
+use super::message_capnp::entry;
+
+#[capnp_conv(entry)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    #[capnp_conv(before_write = "to_trimmed")]
+    pub key: String,
+    pub count: u32,
+}
+
+#[allow(clippy::ptr_arg)]
+fn to_trimmed(key: &String) -> String {
+    key.trim().to_owned()
+}
@@ -0,0 +1,83 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod message_capnp;
+mod message_rust;
+
+use std::{sync::Arc, thread};
+
+use capnp_conv::{Message, WritableExt};
+use message_rust::Entry;
+
+fn entry() -> Entry {
+    Entry {
+        key: String::from("key"),
+        count: 3,
+    }
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn message_test() {
+    assert_send_sync::<Message<Entry>>();
+
+    let message = Message::new(&entry()).unwrap();
+    assert_eq!(message.as_bytes(), entry().to_bytes().unwrap());
+
+    let reader = message.reader().unwrap();
+    let root = reader.get().unwrap();
+    assert_eq!(root.get_key().unwrap().to_str().unwrap(), "key");
+    assert_eq!(root.get_count(), 3);
+
+    assert_eq!(*message.get().unwrap(), entry());
+    assert_eq!(message.clone().into_value().unwrap(), entry());
+    assert_eq!(
+        Message::from_value(entry()).unwrap().into_value().unwrap(),
+        entry()
+    );
+}
+
+#[test]
+fn message_from_value_test() {
+    // The value kept is the one read back, not the value that was written
+    let input = Entry {
+        key: String::from(" key "),
+        ..entry()
+    };
+    let message = Message::from_value(input).unwrap();
+    assert_eq!(*message.get().unwrap(), entry());
+    assert_eq!(message.into_value().unwrap(), entry());
+}
+
+#[test]
+fn message_from_bytes_test() {
+    let mut bytes = entry().to_bytes().unwrap();
+    let len = bytes.len();
+
+    // Bytes after the message are dropped
+    bytes.extend([1, 2, 3]);
+    let message = Message::<Entry>::from_bytes(&bytes).unwrap();
+    assert_eq!(message.as_bytes().len(), len);
+    assert_eq!(*message.get().unwrap(), entry());
+
+    let error = Message::<Entry>::from_bytes(&bytes[..len - 4]).unwrap_err();
+    assert_eq!(
+        error.extra,
+        format!(
+            "capnp_conv: message of {len} bytes ends after {} bytes",
+            len - 4
+        )
+    );
+}
+
+#[test]
+fn message_threads_test() {
+    let message = Arc::new(Message::new(&entry()).unwrap());
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            let message = Arc::clone(&message);
+            scope.spawn(move || assert_eq!(*message.get().unwrap(), entry()));
+        }
+    });
+    assert_eq!(*message.get().unwrap(), entry());
+}
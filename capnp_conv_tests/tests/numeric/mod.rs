@@ -0,0 +1,85 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod numeric_capnp;
+mod numeric_rust;
+
+use capnp::message::TypedBuilder;
+use capnp_conv::{Readable, Writable};
+use numeric_rust::NumericStruct;
+
+use self::numeric_capnp::numeric_struct;
+use crate::{assert_identical, to_message};
+
+fn sample() -> NumericStruct {
+    NumericStruct {
+        count: 42,
+        offset: -7,
+        code: 'é',
+        small: 200,
+        narrow: 255,
+        counts: vec![0, usize::MAX],
+        opt_code: Some('x'),
+        big: u128::MAX - 1,
+        signed: i128::MIN,
+        big_data: 1 << 100,
+        signed_list: vec![-1, 1 << 70],
+    }
+}
+
+fn read_error(edit: impl FnOnce(numeric_struct::Builder)) -> String {
+    let mut builder = to_message(&sample());
+    edit(builder.get_root().unwrap());
+    NumericStruct::read(builder.get_root_as_reader().unwrap())
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn numeric_test() {
+    assert_identical(&sample());
+
+    let builder = to_message(&sample());
+    let reader = builder.get_root_as_reader().unwrap();
+    assert_eq!(reader.get_code(), 0xE9);
+    assert_eq!(reader.get_small(), 200);
+    let signed = reader.get_signed().unwrap();
+    assert_eq!((signed.get_hi(), signed.get_lo()), (1 << 63, 0));
+    let minus_one = reader.get_signed_list().unwrap().get(0);
+    assert_eq!(
+        (minus_one.get_hi(), minus_one.get_lo()),
+        (u64::MAX, u64::MAX)
+    );
+    assert_eq!(reader.get_big_data().unwrap()[3], 0x10);
+}
+
+#[test]
+fn out_of_range_read_test() {
+    assert!(read_error(|mut root| root.set_small(300))
+        .contains("`small` value 300 is out of range for `u8`"));
+    assert!(read_error(|mut root| root.set_code(0xD800))
+        .contains("`code` value 55296 is out of range for `char`"));
+    assert!(read_error(|mut root| root.set_big_data(&[1, 2, 3]))
+        .contains("expected 16 bytes for `u128`, found 3"));
+}
+
+#[test]
+#[should_panic(expected = "`narrow` value 256 is out of range for `u8`, use `try_write`")]
+fn out_of_range_write_test() {
+    to_message(&NumericStruct {
+        narrow: 256,
+        ..sample()
+    });
+}
+
+#[test]
+fn out_of_range_try_write_test() {
+    let input = NumericStruct {
+        narrow: 256,
+        ..sample()
+    };
+    let mut builder = TypedBuilder::<numeric_struct::Owned>::new_default();
+    let error = input.try_write(builder.init_root()).unwrap_err();
+    assert!(error
+        .extra
+        .contains("`narrow` value 256 is out of range for `u8`"));
+}
@@ -0,0 +1,22 @@
+use capnp_conv::capnp_conv;
+
+use super::numeric_capnp::numeric_struct;
+
+#[capnp_conv(numeric_struct)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumericStruct {
+    pub count: usize,
+    pub offset: isize,
+    pub code: char,
+    #[capnp_conv(capnp_type = "UInt32")]
+    pub small: u8,
+    #[capnp_conv(capnp_type = "UInt8")]
+    pub narrow: u32,
+    pub counts: Vec<usize>,
+    pub opt_code: Option<char>,
+    pub big: u128,
+    pub signed: i128,
+    #[capnp_conv(type = "data")]
+    pub big_data: u128,
+    pub signed_list: Vec<i128>,
+}
@@ -3,9 +3,14 @@
 mod optional_capnp;
 mod optional_rust;
 
-use optional_rust::{BasicStruct, TestDefaults, TestDefaultsOptional, TestOptional};
+use capnp_conv::Readable;
+use optional_rust::{
+    BasicStruct, TestDefaults, TestDefaultsOptional, TestDenyMissing, TestFallbacks,
+    TestFallbacksOptional, TestOptional, TestRequired, TestRequiredOptional,
+};
 
-use crate::{assert_identical, assert_match};
+use self::optional_capnp::test_required;
+use crate::{assert_identical, assert_match, to_message};
 
 #[test]
 fn optional_test() {
@@ -79,3 +84,94 @@ fn defaults_test() {
         &defaults,
     );
 }
+
+#[test]
+fn fallbacks_test() {
+    assert_match(
+        &TestFallbacksOptional {
+            region: None,
+            struc: None,
+            list: None,
+            timeout: Some(5),
+        },
+        &TestFallbacks {
+            region: "eu-west".to_string(),
+            struc: BasicStruct { val: 7 },
+            list: vec![1, 2],
+            timeout: 30,
+        },
+    );
+
+    // Set fields are read as usual, even when empty
+    assert_match(
+        &TestFallbacksOptional {
+            region: Some(String::new()),
+            struc: Some(BasicStruct { val: 0 }),
+            list: Some(vec![]),
+            timeout: None,
+        },
+        &TestFallbacks {
+            region: String::new(),
+            struc: BasicStruct { val: 0 },
+            list: vec![],
+            timeout: 30,
+        },
+    );
+}
+
+fn read_required<T: Readable<OwnedType = test_required::Owned>>(
+    input: &TestRequiredOptional,
+) -> capnp::Result<T> {
+    let builder = to_message(input);
+    T::read(builder.get_root_as_reader()?)
+}
+
+#[test]
+fn required_test() {
+    let input = TestRequiredOptional {
+        text: Some(String::new()),
+        struc: Some(BasicStruct { val: 1 }),
+        list: None,
+        prim: None,
+        data: None,
+    };
+    assert_eq!(
+        read_required::<TestRequired>(&input).unwrap(),
+        TestRequired {
+            text: String::new(),
+            struc: BasicStruct { val: 1 },
+            list: vec![],
+            prim: 0,
+            data: vec![],
+        }
+    );
+    assert_eq!(
+        read_required::<TestDenyMissing>(&input).unwrap(),
+        TestDenyMissing {
+            text: String::new(),
+            struc: BasicStruct { val: 1 },
+            list: vec![3],
+            prim: 0,
+            data: None,
+        }
+    );
+
+    let missing_text = TestRequiredOptional {
+        text: None,
+        ..input.clone()
+    };
+    let error = read_required::<TestRequired>(&missing_text).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("required field `text` (capnp `text`) is not set"));
+
+    let missing_struc = TestRequiredOptional {
+        struc: None,
+        ..input
+    };
+    assert!(read_required::<TestRequired>(&missing_struc).is_ok());
+    let error = read_required::<TestDenyMissing>(&missing_struc).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("required field `struc` (capnp `struc`) is not set"));
+}
@@ -1,6 +1,8 @@
 use capnp_conv::capnp_conv;
 
-use super::optional_capnp::{basic_struct, test_defaults, test_optional};
+use super::optional_capnp::{
+    basic_struct, test_defaults, test_fallbacks, test_optional, test_required,
+};
 
 #[capnp_conv(test_optional)]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -35,6 +37,67 @@ pub struct TestDefaultsOptional {
     pub list: Option<Vec<i32>>,
 }
 
+#[capnp_conv(test_fallbacks)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFallbacks {
+    #[capnp_conv(default = "\"eu-west\"")]
+    pub region: String,
+    #[capnp_conv(default = "fallback_struct")]
+    pub struc: BasicStruct,
+    #[capnp_conv(default = vec![1, 2])]
+    pub list: Vec<i32>,
+    #[capnp_conv(skip_read, default = 30)]
+    pub timeout: i32,
+}
+
+#[capnp_conv(test_fallbacks)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFallbacksOptional {
+    pub region: Option<String>,
+    pub struc: Option<BasicStruct>,
+    pub list: Option<Vec<i32>>,
+    pub timeout: Option<i32>,
+}
+
+#[capnp_conv(test_required)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRequired {
+    #[capnp_conv(required)]
+    pub text: String,
+    pub struc: BasicStruct,
+    pub list: Vec<i32>,
+    pub prim: i32,
+    #[capnp_conv(type = "data")]
+    pub data: Vec<u8>,
+}
+
+#[capnp_conv(test_required, deny_missing)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestDenyMissing {
+    pub text: String,
+    pub struc: BasicStruct,
+    #[capnp_conv(default = vec![3])]
+    pub list: Vec<i32>,
+    pub prim: i32,
+    #[capnp_conv(type = "data")]
+    pub data: Option<Vec<u8>>,
+}
+
+#[capnp_conv(test_required)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRequiredOptional {
+    pub text: Option<String>,
+    pub struc: Option<BasicStruct>,
+    pub list: Option<Vec<i32>>,
+    pub prim: Option<i32>,
+    #[capnp_conv(type = "data")]
+    pub data: Option<Vec<u8>>,
+}
+
+pub fn fallback_struct() -> BasicStruct {
+    BasicStruct { val: 7 }
+}
+
 #[capnp_conv(basic_struct)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BasicStruct {
@@ -0,0 +1,147 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod parallel_capnp;
+mod parallel_rust;
+
+use std::collections::HashSet;
+
+use capnp::{
+    message::{ReaderOptions, ReaderSegments},
+    serialize,
+};
+use capnp_conv::{
+    read_parallel, Message, MessageOptions, ReadLimits, Readable, ReadableExt, WritableExt,
+};
+use parallel_rust::{Dataset, Point, TrackedDataset};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::{assert_identical, to_message};
+
+fn point(idx: u16) -> Point {
+    Point {
+        x: f64::from(idx),
+        y: -f64::from(idx) / 2.0,
+        tag: format!("point {idx}"),
+        count: idx,
+    }
+}
+
+/// A pool with several threads, however many cores the tests run on
+fn pool() -> ThreadPool {
+    ThreadPoolBuilder::new().num_threads(4).build().unwrap()
+}
+
+fn dataset(len: u16) -> Dataset {
+    Dataset {
+        points: (0..len).map(point).collect(),
+        small: (0..10).map(point).collect(),
+    }
+}
+
+#[test]
+fn parallel_test() {
+    assert_identical(&dataset(5000));
+    assert_identical(&dataset(10));
+    assert_identical(&Dataset {
+        points: vec![],
+        small: vec![],
+    });
+}
+
+#[test]
+fn parallel_error_test() {
+    let mut message = to_message(&dataset(5000));
+    let mut points = message.get_root().unwrap().get_points().unwrap();
+    points.reborrow().get(3000).set_count(u32::MAX);
+    points.reborrow().get(1500).set_count(u32::MAX);
+    let bytes = serialize::write_message_to_words(message.borrow_inner());
+
+    // The error is that of the first item that fails, whichever chunk is read first
+    pool().install(|| {
+        for _ in 0..10 {
+            let shared = serialize::read_message(bytes.as_slice(), ReaderOptions::new()).unwrap();
+            let error = read_parallel::<Dataset, _>(shared.into_segments(), ReaderOptions::new())
+                .unwrap_err();
+            assert_eq!(error.path_string(), "Dataset.points[1500].count");
+        }
+    });
+
+    // Other reads are not parallel, but read the same
+    let reader = message.get_root_as_reader().unwrap();
+    let error = Dataset::read_with_path(reader).unwrap_err();
+    assert_eq!(error.path_string(), "Dataset.points[1500].count");
+    let error = Dataset::read_with_limits(reader, ReadLimits::default()).unwrap_err();
+    assert_eq!(error.path_string(), "Dataset.points[1500].count");
+}
+
+#[test]
+fn parallel_segments_test() {
+    // The points and their tags are spread over several segments
+    let options = MessageOptions {
+        first_segment_words: 64,
+        ..MessageOptions::default()
+    };
+    let input = dataset(5000);
+    let bytes = options.write_bytes(&input).unwrap();
+    let segments = serialize::read_message(bytes.as_slice(), ReaderOptions::new()).unwrap();
+    assert!(ReaderSegments::len(&segments.into_segments()) > 2);
+
+    let pool = pool();
+    let output = pool
+        .install(|| options.read_bytes::<Dataset>(&bytes))
+        .unwrap();
+    assert_eq!(output, input);
+    let output = pool
+        .install(|| options.read_bytes::<TrackedDataset>(&bytes))
+        .unwrap();
+    let threads: HashSet<_> = output.points.iter().map(|point| point.thread).collect();
+    assert!(threads.len() > 1);
+}
+
+#[test]
+fn parallel_limits_test() {
+    let input = dataset(2000);
+    let message = to_message(&input);
+    let reader = message.get_root_as_reader().unwrap();
+
+    assert_eq!(
+        Dataset::read_with_limits(reader, ReadLimits::default()).unwrap(),
+        input
+    );
+    let limits = ReadLimits {
+        max_list_len: 1000,
+        ..ReadLimits::default()
+    };
+    assert!(Dataset::read_with_limits(reader, limits).is_err());
+}
+
+#[test]
+fn parallel_threads_test() {
+    let bytes = dataset(5000).to_bytes().unwrap();
+    let threads = |dataset: &TrackedDataset| -> HashSet<Option<usize>> {
+        dataset.points.iter().map(|point| point.thread).collect()
+    };
+    let pool = pool();
+
+    // `Message` shares its segments with the threads of the pool
+    let message = Message::<TrackedDataset>::from_bytes(&bytes).unwrap();
+    let output = pool.install(|| message.into_value()).unwrap();
+    assert_eq!(output.points.len(), 5000);
+    assert!(!threads(&output).contains(&None));
+    assert!(threads(&output).len() > 1);
+
+    // As do reads that copy the message
+    let output = pool.install(|| TrackedDataset::from_bytes(&bytes)).unwrap();
+    assert!(threads(&output).len() > 1);
+
+    // Readers and borrowed messages are read on the thread that reads them
+    let output = pool
+        .install(|| TrackedDataset::from_flat_slice(&mut bytes.as_slice()))
+        .unwrap();
+    assert_eq!(threads(&output).len(), 1);
+    let message = Message::<TrackedDataset>::from_bytes(&bytes).unwrap();
+    let output = pool
+        .install(|| TrackedDataset::read(message.reader().unwrap().get().unwrap()))
+        .unwrap();
+    assert_eq!(threads(&output).len(), 1);
+}
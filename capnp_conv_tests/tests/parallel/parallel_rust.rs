@@ -0,0 +1,55 @@
+use std::{thread, time::Duration};
+
+use capnp_conv::capnp_conv;
+
+use super::parallel_capnp::{dataset, point};
+
+#[capnp_conv(dataset)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dataset {
+    #[capnp_conv(parallel)]
+    pub points: Vec<Point>,
+    #[capnp_conv(parallel = 4)]
+    pub small: Vec<Point>,
+}
+
+#[capnp_conv(point)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+    pub tag: String,
+    #[capnp_conv(capnp_type = "UInt32")]
+    pub count: u16,
+}
+
+/// A `Dataset` whose points keep the rayon thread that read them
+#[capnp_conv(dataset)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedDataset {
+    #[capnp_conv(parallel)]
+    pub points: Vec<TrackedPoint>,
+    #[capnp_conv(parallel = 4)]
+    pub small: Vec<TrackedPoint>,
+}
+
+#[capnp_conv(point, after_read = "TrackedPoint::record_thread")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedPoint {
+    #[capnp_conv(capnp_type = "UInt32")]
+    pub count: u16,
+    #[capnp_conv(skip)]
+    pub thread: Option<usize>,
+}
+
+impl TrackedPoint {
+    /// Keeps the rayon thread reading the point, some points take a while to read so that the
+    /// other threads take on the chunks that are left
+    fn record_thread(mut self) -> Self {
+        if self.count.is_multiple_of(100) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        self.thread = rayon::current_thread_index();
+        self
+    }
+}
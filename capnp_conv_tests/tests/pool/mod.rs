@@ -0,0 +1,101 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod pool_capnp;
+mod pool_rust;
+
+use std::thread;
+
+use capnp::Word;
+use capnp_conv::{BuilderPool, MessageOptions, ReadableExt, WritableExt};
+use pool_rust::Batch;
+
+fn batch(id: u64) -> Batch {
+    Batch {
+        id,
+        values: (0..1000).collect(),
+    }
+}
+
+#[test]
+fn builder_pool_test() {
+    let pool = BuilderPool::default();
+    let input = batch(1);
+    let bytes = pool.write_bytes(&input).unwrap();
+    assert_eq!(bytes, input.to_bytes().unwrap());
+    assert_eq!(Batch::from_bytes(&bytes).unwrap(), input);
+    assert!(pool.pooled_segments() > 0);
+
+    // Reused segments write the same bytes
+    let second = batch(2);
+    assert_eq!(
+        pool.write_bytes(&second).unwrap(),
+        second.to_bytes().unwrap()
+    );
+
+    // Reused segments are cleared, so a smaller message is written as by a new builder
+    let small = Batch {
+        id: 3,
+        values: vec![-1; 3],
+    };
+    assert_eq!(pool.write_bytes(&small).unwrap(), small.to_bytes().unwrap());
+
+    pool.clear();
+    assert_eq!(pool.pooled_segments(), 0);
+}
+
+#[test]
+fn builder_pool_threads_test() {
+    let pool = BuilderPool::new(
+        MessageOptions {
+            first_segment_words: 64,
+            ..MessageOptions::default()
+        },
+        BuilderPool::DEFAULT_MAX_POOLED_WORDS,
+    );
+    thread::scope(|scope| {
+        for id in 0..4 {
+            let pool = &pool;
+            scope.spawn(move || {
+                for _ in 0..10 {
+                    let bytes = pool.write_bytes(&batch(id)).unwrap();
+                    assert_eq!(Batch::from_bytes(&bytes).unwrap(), batch(id));
+                }
+            });
+        }
+    });
+    assert!(pool.pooled_segments() > 0);
+}
+
+#[test]
+fn builder_pool_limit_test() {
+    let options = MessageOptions {
+        first_segment_words: 64,
+        ..MessageOptions::default()
+    };
+    let pool = BuilderPool::new(options, 0);
+    pool.write_bytes(&batch(5)).unwrap();
+    assert_eq!(pool.pooled_segments(), 0);
+
+    // Segments are pooled until the next one would not fit
+    let pool = BuilderPool::new(options, 200);
+    pool.write_bytes(&batch(6)).unwrap();
+    assert!(pool.pooled_segments() > 0);
+    assert!(pool.pooled_words() <= 200);
+}
+
+#[test]
+fn scratch_test() {
+    let input = batch(4);
+    let mut scratch = Word::allocate_zeroed_vec(2048);
+    let scratch = Word::words_to_bytes_mut(&mut scratch);
+
+    let mut bytes = Vec::new();
+    input.write_to_with_scratch(scratch, &mut bytes).unwrap();
+    assert_eq!(bytes, input.to_bytes().unwrap());
+
+    // Messages that do not fit continue in allocated segments
+    let message = MessageOptions::default()
+        .write_message_with_scratch(&input, &mut scratch[..64])
+        .unwrap();
+    assert!(message.get_segments_for_output().len() > 1);
+}
@@ -0,0 +1,10 @@
+use capnp_conv::capnp_conv;
+
+use super::pool_capnp::batch;
+
+#[capnp_conv(batch)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch {
+    pub id: u64,
+    pub values: Vec<i64>,
+}
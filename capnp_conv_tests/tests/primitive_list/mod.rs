@@ -0,0 +1,122 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod primitive_list_capnp;
+mod primitive_list_rust;
+
+use capnp::message::{Builder, ReaderOptions, TypedBuilder, TypedReader};
+use capnp_conv::{ReadableExt, Writable, WritableExt};
+use primitive_list_capnp::{sample_structs, samples};
+use primitive_list_rust::Samples;
+
+use crate::{assert_identical, to_message};
+
+#[test]
+fn primitive_list_test() {
+    assert_identical(&Samples {
+        floats: (0..60_000u16).map(|idx| f32::from(idx) / 3.0).collect(),
+        bytes: (0..=u8::MAX).cycle().take(100_001).collect(),
+        signed: (-500..500).map(|idx| idx * i64::from(i32::MAX)).collect(),
+        flags: (0..99).map(|idx| idx % 3 == 0).collect(),
+        nested: vec![vec![], vec![1], (0..1000).collect()],
+    });
+    assert_identical(&Samples {
+        floats: (0..=u16::MAX)
+            .cycle()
+            .take(100_000)
+            .map(|idx| f32::from(idx) / -3.0)
+            .collect(),
+        bytes: vec![],
+        signed: (0..100_000).map(|idx| i64::MAX - idx).collect(),
+        flags: vec![],
+        nested: vec![],
+    });
+    assert_identical(&Samples {
+        floats: vec![],
+        bytes: vec![],
+        signed: vec![],
+        flags: vec![],
+        nested: vec![],
+    });
+}
+
+/// Reads the lists of `input` written with `write` and `try_write` element by element with capnp
+fn assert_layout(input: &Samples) {
+    let bytes = input.to_bytes().unwrap();
+    let reader = capnp::serialize::read_message(bytes.as_slice(), ReaderOptions::new()).unwrap();
+    let reader = TypedReader::<_, samples::Owned>::new(reader);
+    let builder = to_message(input);
+    let mut checked_builder = TypedBuilder::<samples::Owned>::new_default();
+    input.try_write(checked_builder.init_root()).unwrap();
+
+    for root in [
+        reader.get().unwrap(),
+        builder.get_root_as_reader().unwrap(),
+        checked_builder.get_root_as_reader().unwrap(),
+    ] {
+        let floats = root.get_floats().unwrap();
+        assert_eq!(floats.iter().collect::<Vec<_>>(), input.floats);
+        let signed = root.get_signed().unwrap();
+        assert_eq!(signed.iter().collect::<Vec<_>>(), input.signed);
+        let bytes = root.get_bytes().unwrap();
+        assert_eq!(bytes.iter().collect::<Vec<_>>(), input.bytes);
+        let nested = root.get_nested().unwrap();
+        for (idx, list) in (0..).zip(&input.nested) {
+            assert_eq!(nested.get(idx).unwrap().iter().collect::<Vec<_>>(), *list);
+        }
+    }
+}
+
+#[test]
+fn primitive_list_layout_test() {
+    assert_layout(&Samples {
+        floats: vec![1.5, -2.25, f32::MAX],
+        bytes: vec![1, 2, 3],
+        signed: vec![i64::MIN, 0, i64::MAX],
+        flags: vec![true, false, true],
+        nested: vec![vec![7, 8]],
+    });
+
+    // Long lists are written in the standard layout as well
+    let input = Samples {
+        floats: (0..=u16::MAX)
+            .cycle()
+            .take(100_000)
+            .map(|idx| f32::from(idx) / -3.0)
+            .collect(),
+        bytes: (0..=u8::MAX).cycle().take(1000).collect(),
+        signed: (0..100_000)
+            .map(|idx| (idx - 50_000) * 92_233_720_368_547)
+            .collect(),
+        flags: vec![],
+        nested: vec![(0..5000).collect(), vec![], (0..17).rev().collect()],
+    };
+    assert_layout(&input);
+}
+
+#[test]
+fn primitive_list_upgraded_test() {
+    // Lists of structs are read one element at a time, from the first field of each struct
+    let mut message = Builder::new_default();
+    let mut root = message.init_root::<sample_structs::Builder<'_>>();
+    let mut floats = root.reborrow().init_floats(3);
+    for (idx, value) in (0..).zip([0.5, 1.5, 2.5]) {
+        let mut sample = floats.reborrow().get(idx);
+        sample.set_value(value);
+        sample.set_weight(-1.0);
+    }
+    let mut bytes = root.init_bytes(2);
+    bytes.reborrow().get(0).set_value(9);
+    bytes.get(1).set_value(10);
+
+    let output = Samples::from_bytes(&capnp::serialize::write_message_to_words(&message)).unwrap();
+    assert_eq!(
+        output,
+        Samples {
+            floats: vec![0.5, 1.5, 2.5],
+            bytes: vec![9, 10],
+            signed: vec![],
+            flags: vec![],
+            nested: vec![],
+        }
+    );
+}
@@ -0,0 +1,13 @@
+use capnp_conv::capnp_conv;
+
+use super::primitive_list_capnp::samples;
+
+#[capnp_conv(samples)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Samples {
+    pub floats: Vec<f32>,
+    pub bytes: Vec<u8>,
+    pub signed: Vec<i64>,
+    pub flags: Vec<bool>,
+    pub nested: Vec<Vec<u16>>,
+}
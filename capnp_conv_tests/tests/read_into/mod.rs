@@ -0,0 +1,82 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod read_into_capnp;
+mod read_into_rust;
+
+use capnp::text;
+use capnp_conv::Readable;
+use read_into_rust::{Customer, Line, Order, OrderStatus, OrderTotals};
+
+use self::read_into_capnp::Priority;
+use crate::{to_message, INVALID_UTF8};
+
+fn order(id: u64, lines: u32) -> Order {
+    Order {
+        id,
+        customer: Customer {
+            name: format!("customer {id}"),
+            emails: vec![format!("{id}@example.com"), format!("{id}@example.org")],
+        },
+        note: format!("note {id}"),
+        payload: vec![1, 2, 3, 4],
+        lines: (0..lines)
+            .map(|line| Line {
+                sku: format!("sku {id} {line}"),
+                quantity: line,
+            })
+            .collect(),
+        tags: vec![String::from("fragile"), format!("tag {id}")],
+        scores: (0..lines).collect(),
+        coupon: None,
+        status: OrderStatus::Pending(()),
+        totals: OrderTotals {
+            net: id * 100,
+            tax: id * 10,
+        },
+        priority: Priority::Low,
+    }
+}
+
+#[test]
+fn read_into_test() {
+    let mut output = order(1, 3);
+
+    // Fewer list items than are in `output`
+    let mut input = order(2, 1);
+    input.payload = vec![5];
+    input.tags.clear();
+    input.coupon = Some(String::from("SAVE"));
+    input.status = OrderStatus::Shipped(String::from("tracking"));
+    input.priority = Priority::High;
+    let message = to_message(&input);
+    output
+        .read_into(message.get_root_as_reader().unwrap())
+        .unwrap();
+    assert_eq!(output, input);
+
+    // More list items than are in `output`
+    let input = order(3, 5);
+    let message = to_message(&input);
+    output
+        .read_into(message.get_root_as_reader().unwrap())
+        .unwrap();
+    assert_eq!(output, input);
+}
+
+#[test]
+fn read_into_error_test() {
+    let mut message = to_message(&order(2, 3));
+    let mut lines = message.get_root().unwrap().get_lines().unwrap();
+    lines
+        .reborrow()
+        .get(1)
+        .set_sku(text::Reader::from(INVALID_UTF8));
+
+    let mut output = order(1, 3);
+    let error = output
+        .read_into(message.get_root_as_reader().unwrap())
+        .unwrap_err();
+    assert_eq!(error.path_string(), "Order.lines[1].sku");
+    let expected = Order::read_with_path(message.get_root_as_reader().unwrap()).unwrap_err();
+    assert_eq!(error.path, expected.path);
+}
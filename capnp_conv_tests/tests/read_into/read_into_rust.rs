@@ -0,0 +1,51 @@
+use capnp_conv::capnp_conv;
+
+use super::read_into_capnp::{customer, line, order, Priority};
+
+#[capnp_conv(order)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+    pub id: u64,
+    pub customer: Customer,
+    pub note: String,
+    #[capnp_conv(type = "data")]
+    pub payload: Vec<u8>,
+    pub lines: Vec<Line>,
+    pub tags: Vec<String>,
+    pub scores: Vec<u32>,
+    pub coupon: Option<String>,
+    #[capnp_conv(type = "union")]
+    pub status: OrderStatus,
+    #[capnp_conv(type = "group")]
+    pub totals: OrderTotals,
+    #[capnp_conv(type = "enum")]
+    pub priority: Priority,
+}
+
+#[capnp_conv(customer)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Customer {
+    pub name: String,
+    pub emails: Vec<String>,
+}
+
+#[capnp_conv(line)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Line {
+    pub sku: String,
+    pub quantity: u32,
+}
+
+#[capnp_conv(order::status)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderStatus {
+    Pending(()),
+    Shipped(String),
+}
+
+#[capnp_conv(order::totals)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrderTotals {
+    pub net: u64,
+    pub tax: u64,
+}
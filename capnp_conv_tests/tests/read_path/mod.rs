@@ -0,0 +1,88 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod read_path_capnp;
+mod read_path_rust;
+
+use capnp::text;
+use capnp_conv::{PathSegment, ReadError, Readable};
+use read_path_rust::{Inner, Outer, OuterBody};
+
+use self::read_path_capnp::outer;
+use crate::{assert_identical, to_message, INVALID_UTF8};
+
+fn sample() -> Outer {
+    let inner = Inner {
+        names: vec![String::from("a"), String::from("b")],
+        groups: vec![
+            vec![String::from("c")],
+            vec![String::from("d"), String::from("e")],
+        ],
+    };
+    Outer {
+        inner: inner.clone(),
+        body: OuterBody::Inners(vec![inner.clone(), inner.clone(), inner]),
+    }
+}
+
+fn read_error(edit: impl FnOnce(outer::Builder)) -> ReadError {
+    let mut builder = to_message(&sample());
+    edit(builder.get_root().unwrap());
+    Outer::read_with_path(builder.get_root_as_reader().unwrap()).unwrap_err()
+}
+
+#[test]
+fn read_path_test() {
+    assert_identical(&sample());
+
+    let error = read_error(|root| {
+        let mut names = root.get_inner().unwrap().get_names().unwrap();
+        names.set(1, text::Reader::from(INVALID_UTF8));
+    });
+    assert_eq!(error.path_string(), "Outer.inner.names[1]");
+    assert_eq!(error.root, Some("Outer"));
+    assert_eq!(
+        error.path,
+        vec![
+            PathSegment::Index(1),
+            PathSegment::Field {
+                rust_name: "names",
+                capnp_name: "names",
+            },
+            PathSegment::Field {
+                rust_name: "inner",
+                capnp_name: "inner",
+            },
+        ]
+    );
+
+    let error = read_error(|root| {
+        let Ok(outer::Inners(inners)) = root.which() else {
+            unreachable!("sample has inners")
+        };
+        let mut names = inners.unwrap().get(2).get_names().unwrap();
+        names.set(0, text::Reader::from(INVALID_UTF8));
+    });
+    assert_eq!(error.path_string(), "Outer.body.Inners[2].names[0]");
+
+    let error = read_error(|root| {
+        let groups = root.get_inner().unwrap().get_groups().unwrap();
+        groups
+            .get(1)
+            .unwrap()
+            .set(1, text::Reader::from(INVALID_UTF8));
+    });
+    assert_eq!(error.path_string(), "Outer.inner.groups[1][1]");
+
+    let mut builder = to_message(&sample());
+    let mut names = builder
+        .get_root()
+        .unwrap()
+        .get_inner()
+        .unwrap()
+        .get_names()
+        .unwrap();
+    names.set(0, text::Reader::from(INVALID_UTF8));
+    let error = Outer::read(builder.get_root_as_reader().unwrap()).unwrap_err();
+    assert!(error.to_string().contains("non-utf8"));
+    assert!(error.to_string().ends_with("at `Outer.inner.names[0]`"));
+}
@@ -0,0 +1,25 @@
+use capnp_conv::capnp_conv;
+
+use super::read_path_capnp::{inner, outer};
+
+#[capnp_conv(inner)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inner {
+    pub names: Vec<String>,
+    pub groups: Vec<Vec<String>>,
+}
+
+#[capnp_conv(outer)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outer {
+    pub inner: Inner,
+    #[capnp_conv(type = "unnamed_union")]
+    pub body: OuterBody,
+}
+
+#[capnp_conv(outer)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OuterBody {
+    Empty(()),
+    Inners(Vec<Inner>),
+}
@@ -0,0 +1,66 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod remote_enum_capnp;
+mod remote_enum_rust;
+
+use capnp_conv::Readable;
+use remote_enum_rust::{NewEnum, NewStruct, OldEnum, OldStruct};
+
+use self::remote_enum_capnp::{new_struct, old_struct};
+use crate::{assert_identical, to_message};
+
+fn new_to_old(input: &NewStruct) -> OldStruct {
+    let builder = to_message(input);
+    let reader = builder
+        .borrow_inner()
+        .get_root_as_reader::<old_struct::Reader>()
+        .unwrap();
+    OldStruct::read(reader).unwrap()
+}
+
+fn old_to_new(input: &OldStruct) -> NewStruct {
+    let builder = to_message(input);
+    let reader = builder
+        .borrow_inner()
+        .get_root_as_reader::<new_struct::Reader>()
+        .unwrap();
+    NewStruct::read(reader).unwrap()
+}
+
+#[test]
+fn unknown_enumerant_test() {
+    assert_identical(&OldStruct {
+        enum_val: OldEnum::Val2,
+        enum_list: vec![OldEnum::Val1, OldEnum::Unknown(7)],
+        union_enum: Some(OldEnum::Unknown(5)),
+        union_void: None,
+    });
+
+    let input = NewStruct {
+        enum_val: NewEnum::Val3,
+        enum_list: vec![NewEnum::Val1, NewEnum::Val3],
+        union_enum: Some(NewEnum::Val3),
+        union_void: None,
+    };
+    let old = new_to_old(&input);
+    assert_eq!(
+        old,
+        OldStruct {
+            enum_val: OldEnum::Unknown(2),
+            enum_list: vec![OldEnum::Val1, OldEnum::Unknown(2)],
+            union_enum: Some(OldEnum::Unknown(2)),
+            union_void: None,
+        }
+    );
+    assert_eq!(old_to_new(&old), input);
+}
+
+#[test]
+fn ordinal_conversion_test() {
+    assert_eq!(u16::from(OldEnum::Val2), 1);
+    assert_eq!(u16::from(OldEnum::Unknown(9)), 9);
+    assert_eq!(OldEnum::try_from(0), Ok(OldEnum::Val1));
+    assert_eq!(OldEnum::try_from(9), Ok(OldEnum::Unknown(9)));
+    assert_eq!(NewEnum::try_from(2), Ok(NewEnum::Val3));
+    assert!(NewEnum::try_from(3).is_err());
+}
@@ -0,0 +1,46 @@
+use capnp_conv::capnp_conv;
+
+use super::remote_enum_capnp::{self, new_struct, old_struct};
+
+#[capnp_conv(remote_enum_capnp::OldEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OldEnum {
+    Val1,
+    Val2,
+    #[capnp_conv(unknown)]
+    Unknown(u16),
+}
+
+#[capnp_conv(remote_enum_capnp::NewEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewEnum {
+    Val1,
+    Val2,
+    Val3,
+}
+
+#[capnp_conv(old_struct)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OldStruct {
+    #[capnp_conv(type = "enum_remote")]
+    pub enum_val: OldEnum,
+    #[capnp_conv(type = "enum_remote")]
+    pub enum_list: Vec<OldEnum>,
+    #[capnp_conv(union_variant, type = "enum_remote")]
+    pub union_enum: Option<OldEnum>,
+    #[capnp_conv(union_variant)]
+    pub union_void: Option<()>,
+}
+
+#[capnp_conv(new_struct)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewStruct {
+    #[capnp_conv(type = "enum_remote")]
+    pub enum_val: NewEnum,
+    #[capnp_conv(type = "enum_remote")]
+    pub enum_list: Vec<NewEnum>,
+    #[capnp_conv(union_variant, type = "enum_remote")]
+    pub union_enum: Option<NewEnum>,
+    #[capnp_conv(union_variant)]
+    pub union_void: Option<()>,
+}
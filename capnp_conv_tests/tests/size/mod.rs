@@ -0,0 +1,109 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod size_capnp;
+mod size_rust;
+
+use capnp::message::HeapAllocator;
+use capnp_conv::{MessageOptions, Writable, WritableExt};
+use size_rust::{Kind, Point, Shape, Style};
+
+use self::size_capnp::Color;
+
+fn point(label: &str) -> Point {
+    Point {
+        x: 1,
+        y: 2,
+        label: String::from(label),
+    }
+}
+
+fn shape(kind: Kind) -> Shape {
+    Shape {
+        name: String::from("a shape with a name"),
+        blob: vec![1; 13],
+        origin: point("origin"),
+        points: vec![point("a"), point("a longer label")],
+        flags: vec![true; 65],
+        codes: vec![7; 5],
+        colors: vec![Color::Red, Color::Green, Color::Red],
+        tags: vec![String::new(), String::from("seven77")],
+        grid: vec![vec![1, 2, 3], vec![]],
+        big: vec![1, 2],
+        note: None,
+        style: Style {
+            color: Color::Green,
+            caption: String::from("caption"),
+        },
+        kind,
+    }
+}
+
+/// The words of the message holding `value`, which has a root pointer
+fn message_words(value: &impl Writable) -> u64 {
+    let mut message = capnp::message::Builder::new(HeapAllocator::new());
+    value.write(message.init_root());
+    message
+        .get_segments_for_output()
+        .iter()
+        .map(|segment| segment.len() as u64 / 8)
+        .sum()
+}
+
+#[test]
+fn encoded_size_test() {
+    for value in [
+        shape(Kind::Circle(1.5)),
+        shape(Kind::Polygon(vec![point("b"); 3])),
+        Shape {
+            note: Some(String::from("note")),
+            ..shape(Kind::Polygon(vec![]))
+        },
+    ] {
+        assert_eq!(value.encoded_size_words() + 1, message_words(&value));
+    }
+    assert_eq!(point("").encoded_size_words(), 3);
+}
+
+#[test]
+fn sized_message_test() {
+    let value = Shape {
+        points: vec![point("a point"); 1000],
+        ..shape(Kind::Circle(1.5))
+    };
+    let options = MessageOptions::sized_for(&value);
+    assert_eq!(
+        u64::from(options.first_segment_words),
+        value.encoded_size_words() + 1
+    );
+
+    let segment = value.to_single_segment_bytes().unwrap();
+    assert_eq!(segment.len() as u64, 8 * (value.encoded_size_words() + 1));
+    assert!(MessageOptions::default()
+        .write_single_segment(&value)
+        .is_err());
+}
+
+/// Checks that `value` is written to a single segment of its estimate and the root pointer
+fn assert_sized_segment(value: &impl Writable) {
+    let message = MessageOptions::sized_for(value)
+        .write_message(value)
+        .unwrap();
+    let segments = message.get_segments_for_output();
+    assert_eq!(segments.len(), 1);
+    assert_eq!(
+        segments[0].len() as u64,
+        8 * (value.encoded_size_words() + 1)
+    );
+}
+
+#[test]
+fn sized_segments_test() {
+    // Values smaller than the default first segment get a segment of their own size
+    assert_eq!(MessageOptions::sized_for(&point("")).first_segment_words, 4);
+    assert_sized_segment(&point(""));
+    assert_sized_segment(&shape(Kind::Circle(1.5)));
+    assert_sized_segment(&Shape {
+        points: vec![point("a point"); 1000],
+        ..shape(Kind::Circle(1.5))
+    });
+}
@@ -0,0 +1,49 @@
+use capnp_conv::capnp_conv;
+
+use super::size_capnp::{point, shape, Color};
+
+#[capnp_conv(point)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+    pub label: String,
+}
+
+#[capnp_conv(shape)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape {
+    pub name: String,
+    #[capnp_conv(type = "data")]
+    pub blob: Vec<u8>,
+    pub origin: Point,
+    pub points: Vec<Point>,
+    pub flags: Vec<bool>,
+    pub codes: Vec<u16>,
+    #[capnp_conv(type = "enum")]
+    pub colors: Vec<Color>,
+    pub tags: Vec<String>,
+    pub grid: Vec<Vec<i64>>,
+    #[capnp_conv(type = "data")]
+    pub big: Vec<u128>,
+    pub note: Option<String>,
+    #[capnp_conv(type = "group")]
+    pub style: Style,
+    #[capnp_conv(type = "unnamed_union")]
+    pub kind: Kind,
+}
+
+#[capnp_conv(shape::style)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    #[capnp_conv(type = "enum")]
+    pub color: Color,
+    pub caption: String,
+}
+
+#[capnp_conv(shape)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind {
+    Circle(f64),
+    Polygon(Vec<Point>),
+}
@@ -0,0 +1,84 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod stream_list_capnp;
+mod stream_list_rust;
+
+use capnp::{message::Builder, serialize};
+use capnp_conv::{write_struct_list, ReadableExt};
+use stream_list_capnp::table;
+use stream_list_rust::{Row, Table};
+
+fn row(id: u32) -> Row {
+    Row {
+        id: u64::from(id),
+        label: format!("row {id}"),
+    }
+}
+
+/// Reports `len` items, but yields `actual`
+struct Miscounted {
+    len: usize,
+    actual: std::ops::Range<u32>,
+}
+
+impl Iterator for Miscounted {
+    type Item = Row;
+
+    fn next(&mut self) -> Option<Row> {
+        self.actual.next().map(row)
+    }
+}
+
+impl ExactSizeIterator for Miscounted {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+fn write_table(rows: impl ExactSizeIterator<Item = Row>) -> capnp::Result<Vec<u8>> {
+    let mut message = Builder::new_default();
+    let mut root = message.init_root::<table::Builder<'_>>();
+    root.set_name("table");
+    write_struct_list::<Row>(rows, "rows", |len| root.reborrow().init_rows(len))?;
+    Ok(serialize::write_message_to_words(&message))
+}
+
+#[test]
+fn stream_list_test() {
+    let expected = Table {
+        name: String::from("table"),
+        rows: (0..1000).map(row).collect(),
+    };
+
+    // Rows are made as they are written
+    let bytes = write_table((0..1000).map(row)).unwrap();
+    assert_eq!(Table::from_bytes(&bytes).unwrap(), expected);
+
+    // Borrowed rows are written as well
+    let mut message = Builder::new_default();
+    let mut root = message.init_root::<table::Builder<'_>>();
+    write_struct_list::<Row>(&expected.rows, "rows", |len| root.reborrow().init_rows(len)).unwrap();
+    root.set_name("table");
+    let output = Table::from_bytes(&serialize::write_message_to_words(&message)).unwrap();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn stream_list_length_test() {
+    let error = write_table(Miscounted {
+        len: 3,
+        actual: 0..2,
+    })
+    .unwrap_err();
+    assert_eq!(error.extra, "capnp_conv: `rows` items ended after 2 of 3");
+
+    let error = write_table(Miscounted {
+        len: 3,
+        actual: 0..4,
+    })
+    .unwrap_err();
+    assert_eq!(
+        error.extra,
+        "capnp_conv: `rows` has more items than its length of 3"
+    );
+}
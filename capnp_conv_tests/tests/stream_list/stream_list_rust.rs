@@ -0,0 +1,17 @@
+use capnp_conv::capnp_conv;
+
+use super::stream_list_capnp::{row, table};
+
+#[capnp_conv(table)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub name: String,
+    pub rows: Vec<Row>,
+}
+
+#[capnp_conv(row)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub id: u64,
+    pub label: String,
+}
@@ -1,12 +1,46 @@
+mod bytes;
+mod coded_enum;
 mod common;
+mod compile_fail;
+mod flags;
+mod hooks;
+mod lazy_list;
+mod lenient;
+mod limits;
+mod message;
+mod numeric;
 mod optional;
+mod parallel;
+mod pool;
+mod primitive_list;
+mod read_into;
+mod read_path;
+mod remote_enum;
+mod size;
+mod stream_list;
+mod union_other;
 mod union_variant;
+mod view;
 
 use std::fmt::Debug;
 
 use capnp::{message::TypedBuilder, traits::Owned};
 use capnp_conv::{Readable, Writable};
 
+/// Text that is not valid UTF-8, to make reads of text fail
+const INVALID_UTF8: &[u8] = &[b'a', 0xFF];
+
+/// Writes `value` to the root of a new message
+fn to_message<T, Cap>(value: &T) -> TypedBuilder<Cap>
+where
+    T: Writable<OwnedType = Cap>,
+    Cap: Owned,
+{
+    let mut builder = TypedBuilder::<Cap>::new_default();
+    value.write(builder.init_root());
+    builder
+}
+
 fn assert_identical<T, CapT>(input: &T)
 where
     T: PartialEq + Debug + Writable<OwnedType = CapT> + Readable<OwnedType = CapT>,
@@ -21,8 +55,7 @@ where
     Y: PartialEq + Debug + Writable<OwnedType = Cap> + Readable<OwnedType = Cap>,
     Cap: Owned,
 {
-    let mut builder = TypedBuilder::<Cap>::new_default();
-    input.write(builder.init_root());
+    let builder = to_message(input);
 
     let output = Y::read(builder.get_root_as_reader().unwrap()).unwrap();
 
@@ -0,0 +1,114 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod union_other_capnp;
+mod union_other_rust;
+
+use capnp::message::TypedBuilder;
+use capnp_conv::{Readable, Writable};
+use union_other_rust::{NewStruct, NewStructNamedUnion, OldStruct, OldStructNamedUnion};
+
+use self::union_other_capnp::{new_struct, old_struct};
+use crate::{assert_identical, assert_match, to_message};
+
+fn new_to_old(input: &NewStruct) -> OldStruct {
+    let builder = to_message(input);
+    let reader = builder
+        .borrow_inner()
+        .get_root_as_reader::<old_struct::Reader>()
+        .unwrap();
+    OldStruct::read(reader).unwrap()
+}
+
+fn old_to_new(input: &OldStruct) -> NewStruct {
+    let builder = to_message(input);
+    let reader = builder
+        .borrow_inner()
+        .get_root_as_reader::<new_struct::Reader>()
+        .unwrap();
+    NewStruct::read(reader).unwrap()
+}
+
+#[test]
+fn known_discriminant_test() {
+    assert_identical(&OldStruct {
+        text_val: "text".to_owned(),
+        union_void: None,
+        union_text: Some("union".to_owned()),
+        union_other: None,
+        named_union: OldStructNamedUnion::Val2(3),
+    });
+}
+
+#[test]
+fn unknown_discriminant_test() {
+    let old = new_to_old(&NewStruct {
+        text_val: "text".to_owned(),
+        union_void: None,
+        union_text: None,
+        union_u64: Some(7),
+        named_union: NewStructNamedUnion::Val3("new".to_owned()),
+    });
+    assert_eq!(
+        old,
+        OldStruct {
+            text_val: "text".to_owned(),
+            union_void: None,
+            union_text: None,
+            union_other: Some(2),
+            named_union: OldStructNamedUnion::Other(2),
+        }
+    );
+}
+
+#[test]
+fn local_other_discriminant_test() {
+    // Discriminants known to the local schema are written as the default of their member
+    assert_match(
+        &OldStruct {
+            text_val: String::new(),
+            union_void: None,
+            union_text: None,
+            union_other: Some(1),
+            named_union: OldStructNamedUnion::Other(0),
+        },
+        &OldStruct {
+            text_val: String::new(),
+            union_void: None,
+            union_text: Some(String::new()),
+            union_other: None,
+            named_union: OldStructNamedUnion::Val1(()),
+        },
+    );
+}
+
+#[test]
+fn unknown_discriminant_round_trip_test() {
+    let old = OldStruct {
+        text_val: "text".to_owned(),
+        union_void: None,
+        union_text: None,
+        union_other: Some(2),
+        named_union: OldStructNamedUnion::Other(2),
+    };
+    let new = old_to_new(&old);
+    assert_eq!(
+        new,
+        NewStruct {
+            text_val: "text".to_owned(),
+            union_void: None,
+            union_text: None,
+            union_u64: Some(0),
+            named_union: NewStructNamedUnion::Val3(String::new()),
+        }
+    );
+
+    // The discriminants are written back unchanged, by `write` and `try_write` alike
+    assert_eq!(new_to_old(&new), old);
+    assert_eq!(old_to_new(&new_to_old(&new)), new);
+    let mut builder = TypedBuilder::<old_struct::Owned>::new_default();
+    old.try_write(builder.init_root()).unwrap();
+    assert_eq!(
+        OldStruct::read(builder.get_root_as_reader().unwrap()).unwrap(),
+        old
+    );
+}
@@ -0,0 +1,48 @@
+use capnp_conv::capnp_conv;
+
+use super::union_other_capnp::{new_struct, old_struct};
+
+#[capnp_conv(old_struct)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OldStruct {
+    pub text_val: String,
+    #[capnp_conv(union_variant)]
+    pub union_void: Option<()>,
+    #[capnp_conv(union_variant)]
+    pub union_text: Option<String>,
+    #[capnp_conv(other)]
+    pub union_other: Option<u16>,
+    #[capnp_conv(type = "union")]
+    pub named_union: OldStructNamedUnion,
+}
+
+#[capnp_conv(old_struct::named_union)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OldStructNamedUnion {
+    Val1(()),
+    Val2(u32),
+    #[capnp_conv(other)]
+    Other(u16),
+}
+
+#[capnp_conv(new_struct)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewStruct {
+    pub text_val: String,
+    #[capnp_conv(union_variant)]
+    pub union_void: Option<()>,
+    #[capnp_conv(union_variant)]
+    pub union_text: Option<String>,
+    #[capnp_conv(union_variant)]
+    pub union_u64: Option<u64>,
+    #[capnp_conv(type = "union")]
+    pub named_union: NewStructNamedUnion,
+}
+
+#[capnp_conv(new_struct::named_union)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NewStructNamedUnion {
+    Val1(()),
+    Val2(u32),
+    Val3(String),
+}
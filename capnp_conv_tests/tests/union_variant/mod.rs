@@ -3,10 +3,12 @@
 mod union_variant_capnp;
 mod union_variant_rust;
 
+use capnp::message::TypedBuilder;
+use capnp_conv::{Readable, Writable};
 use union_variant_rust::{BasicStruct, UnionStruct};
 
-use self::union_variant_rust::UnionStructPure;
-use crate::assert_identical;
+use self::{union_variant_capnp::union_struct, union_variant_rust::UnionStructPure};
+use crate::{assert_identical, to_message};
 
 #[test]
 fn union_variant_test() {
@@ -29,3 +31,35 @@ fn union_variant_test() {
         union_val2: Some(BasicStruct { val: 10 }),
     });
 }
+
+fn both_set() -> UnionStruct {
+    UnionStruct {
+        prim: 5,
+        union_val1: Some("t".to_owned()),
+        union_val2: Some(BasicStruct { val: 10 }),
+    }
+}
+
+#[test]
+fn multiple_union_variants_try_write_test() {
+    let mut builder = TypedBuilder::<union_struct::Owned>::new_default();
+    let error = both_set().try_write(builder.init_root()).unwrap_err();
+    assert_eq!(
+        error.extra,
+        "capnp_conv: `UnionStruct` has more than one union field set: `union_val1`, `union_val2`"
+    );
+}
+
+#[test]
+fn multiple_union_variants_write_test() {
+    let builder = to_message(&both_set());
+    let output = UnionStruct::read(builder.get_root_as_reader().unwrap()).unwrap();
+    assert_eq!(
+        output,
+        UnionStruct {
+            prim: 5,
+            union_val1: None,
+            union_val2: Some(BasicStruct { val: 10 }),
+        }
+    );
+}
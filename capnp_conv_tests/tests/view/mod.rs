@@ -0,0 +1,88 @@
+#[allow(unused, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+mod view_capnp;
+mod view_rust;
+
+use capnp::text;
+use capnp_conv::{Readable, Viewable};
+use view_rust::{Author, Document, DocumentView, Section};
+
+use crate::{to_message, INVALID_UTF8};
+
+fn document() -> Document {
+    Document {
+        title: String::from("Views"),
+        version: 7,
+        author: Author {
+            name: String::from("Ada"),
+            email: String::from("ada@example.com"),
+        },
+        sections: vec![
+            Section {
+                heading: String::from("One"),
+                body: String::from("First"),
+            },
+            Section {
+                heading: String::from("Two"),
+                body: String::from("Second"),
+            },
+        ],
+        tags: vec![String::from("docs")],
+        summary: String::from("  short  "),
+        editor: None,
+        draft: None,
+        published_at: Some(1_700_000_000),
+        views: 0,
+    }
+}
+
+#[test]
+fn view_test() {
+    let input = document();
+    let message = to_message(&input);
+    let view = DocumentView::new(message.get_root_as_reader().unwrap());
+
+    assert_eq!(view.title().unwrap(), "Views");
+    assert_eq!(view.version().unwrap(), 7);
+    assert_eq!(view.sections().unwrap(), input.sections);
+    assert_eq!(view.tags().unwrap(), input.tags);
+    assert_eq!(view.summary().unwrap(), "short");
+    assert_eq!(view.draft().unwrap(), None);
+    assert_eq!(view.published_at().unwrap(), Some(1_700_000_000));
+
+    let author = view.author().unwrap();
+    assert_eq!(author.name().unwrap(), "Ada");
+    assert_eq!(author.email().unwrap(), "ada@example.com");
+    assert_eq!(author.read().unwrap(), input.author);
+    // Unset optional structs are read as `None` like any other optional field
+    assert_eq!(view.editor().unwrap(), None);
+
+    let expected = Document {
+        summary: String::from("short"),
+        ..input
+    };
+    assert_eq!(view.read().unwrap(), expected);
+    let view = Document::view(view.reader());
+    assert_eq!(view.read().unwrap(), expected);
+}
+
+#[test]
+fn view_error_test() {
+    let mut message = to_message(&document());
+    let mut author = message.get_root().unwrap().get_author().unwrap();
+    author.set_name(text::Reader::from(INVALID_UTF8));
+    let view = DocumentView::new(message.get_root_as_reader().unwrap());
+
+    // Only the fields that are read fail
+    assert_eq!(view.title().unwrap(), "Views");
+    let author = view.author().unwrap();
+    assert_eq!(author.email().unwrap(), "ada@example.com");
+    let error = author.name().unwrap_err();
+    assert_eq!(error.path_string(), "Author.name");
+    assert!(error.to_string().contains("non-utf8"));
+
+    let error = view.read().unwrap_err();
+    assert_eq!(error.path_string(), "Document.author.name");
+    let expected = Document::read_with_path(message.get_root_as_reader().unwrap()).unwrap_err();
+    assert_eq!(error.path, expected.path);
+}
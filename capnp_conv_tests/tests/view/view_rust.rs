@@ -0,0 +1,42 @@
+use capnp_conv::capnp_conv;
+
+use super::view_capnp::{author, document, section};
+
+#[capnp_conv(document, view)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub title: String,
+    #[capnp_conv(name = "revision", capnp_type = "UInt32")]
+    pub version: u16,
+    pub author: Author,
+    pub sections: Vec<Section>,
+    pub tags: Vec<String>,
+    #[capnp_conv(after_read = "trimmed")]
+    pub summary: String,
+    pub editor: Option<Author>,
+    #[capnp_conv(union_variant)]
+    pub draft: Option<()>,
+    #[capnp_conv(union_variant)]
+    pub published_at: Option<u64>,
+    #[capnp_conv(skip)]
+    pub views: u32,
+}
+
+#[capnp_conv(author, view)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Author {
+    pub name: String,
+    pub email: String,
+}
+
+#[capnp_conv(section)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub heading: String,
+    pub body: String,
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn trimmed(text: String) -> String {
+    text.trim().to_owned()
+}
@@ -1,12 +1,12 @@
 mod rust_types;
+#[allow(clippy::all, clippy::pedantic)]
 pub mod example_capnp {
     include!(concat!(env!("OUT_DIR"), "/", "example_capnp.rs"));
 }
 
 use std::vec;
 
-use capnp::message::TypedBuilder;
-use capnp_conv::{Readable, Writable};
+use capnp_conv::{ReadableExt, WritableExt};
 use example_capnp as capnp_types;
 
 #[allow(clippy::print_stdout)]
@@ -37,15 +37,9 @@ fn main() {
         unnamed_union: rust_types::ExampleUnnamedUnion::Val2(basic_struct.clone()),
     };
 
-    let mut builder = TypedBuilder::<
-        capnp_types::example_struct::Owned<capnp_types::basic_struct::Owned>,
-    >::new_default();
+    let bytes = input.to_bytes().unwrap();
 
-    input.write(builder.init_root());
-
-    let reader = builder.get_root_as_reader().unwrap();
-
-    let output = rust_types::ExampleStruct::<rust_types::BasicStruct>::read(reader).unwrap();
+    let output = rust_types::ExampleStruct::<rust_types::BasicStruct>::from_bytes(&bytes).unwrap();
 
     println!("Input == Output: {}", input == output);
 }